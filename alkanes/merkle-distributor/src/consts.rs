@@ -1,2 +0,0 @@
-use bitcoin::Network;
-pub const DEPLOYMENT_NETWORK: Network = Network::Regtest;
@@ -1,7 +1,6 @@
 //! Merkle distributor contract
 //!
 //! Created by mork1e
-pub mod consts;
 pub mod schemas;
 pub mod token;
 pub mod utils;
@@ -9,7 +8,7 @@ pub mod utils;
 use alkanes_runtime::{
     declare_alkane, message::MessageDispatch, runtime::AlkaneResponder, storage::StoragePointer,
 };
-use bitcoin::{Address, Transaction};
+use bitcoin::{Address, Network, Transaction};
 
 use alkanes_support::{parcel::AlkaneTransfer, response::CallResponse};
 use anyhow::{anyhow, ensure, Context, Result};
@@ -24,8 +23,10 @@ use token::MintableToken;
 use utils::{extract_witness_payload, get_byte_array_from_inputs};
 
 use crate::{
-    consts::DEPLOYMENT_NETWORK,
-    schemas::{SchemaAlkaneId, SchemaMerkleLeaf, SchemaMerkleProof},
+    schemas::{
+        SchemaAlkaneId, SchemaClaimTotals, SchemaMerkleLeaf, SchemaMerkleProof,
+        SchemaSweepBeneficiary, SchemaSweepRecord,
+    },
     utils::{calc_merkle_root, decode_from_ctx, decode_from_vec},
 };
 
@@ -47,6 +48,31 @@ impl MerkleDistributor {
         StoragePointer::from_keyword("/used").select(leaf_bytes)
     }
 
+    //Running total of leaf amounts paid out by Claim
+    fn get_claimed_pointer(&self) -> StoragePointer {
+        StoragePointer::from_keyword("/claimed")
+    }
+
+    //Empty until Sweep has run
+    fn get_sweep_pointer(&self) -> StoragePointer {
+        StoragePointer::from_keyword("/sweep")
+    }
+
+    fn get_params_value(&self) -> Result<SchemaInitializeMerkleDistributorParameters> {
+        let params_bytes = (*self.get_merkle_root_pointer().get()).clone();
+        decode_from_vec!(params_bytes, SchemaInitializeMerkleDistributorParameters)
+            .context("MERKLE DISTRIBUTOR: failed to decode initialization params")
+    }
+
+    fn get_sweep_value(&self) -> Result<Option<SchemaSweepRecord>> {
+        let bytes = (*self.get_sweep_pointer().get()).clone();
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(decode_from_vec!(bytes, SchemaSweepRecord)?))
+    }
+
     fn collapse_transfers_for_alkane(
         &self,
         alkane: SchemaAlkaneId,
@@ -76,16 +102,7 @@ impl MerkleDistributor {
     }
 
     fn validate_proof(&self, proof: &SchemaMerkleProof) -> Result<bool> {
-        let params_bytes = {
-            let ptr = self.get_merkle_root_pointer();
-            // StoragePointer::get() returns Arc<[u8]>, clone to Vec<u8>
-            (*ptr.get()).clone()
-        };
-
-        let params = decode_from_vec!(params_bytes, SchemaInitializeMerkleDistributorParameters)
-            .context(
-            "MERKLE DISTRIBUTOR: failed to decode initialization params when running proof check",
-        )?;
+        let params = self.get_params_value()?;
 
         let root_from_proof = calc_merkle_root(&proof.leaf, &proof.proofs);
 
@@ -140,6 +157,12 @@ enum MerkleDistributorMessage {
     #[opcode(107)]
     GetInitializationParams,
 
+    #[opcode(108)]
+    Sweep,
+
+    #[opcode(109)]
+    GetClaimTotals,
+
     #[opcode(1000)]
     #[returns(Vec<u8>)]
     GetData,
@@ -191,7 +214,7 @@ impl MerkleDistributor {
         let used_leaf_check = ptr_used_leaf.get_value::<u8>();
 
         ensure!(
-            used_leaf_check != 0u8,
+            used_leaf_check == 0u8,
             "MERKLE DISTRIBUTOR: This leaf has already been used to claim"
         );
         let ok = self.validate_proof(&merkle_proof)?;
@@ -205,12 +228,7 @@ impl MerkleDistributor {
         let ctx = self.context()?;
         let mut response = CallResponse::forward(&ctx.incoming_alkanes);
 
-        let params_bytes = {
-            let ptr = self.get_merkle_root_pointer();
-            (*ptr.get()).clone()
-        };
-
-        response.data = params_bytes;
+        response.data = borsh::to_vec(&self.get_params_value()?)?;
         Ok(response)
     }
 
@@ -241,7 +259,7 @@ impl MerkleDistributor {
         let used_leaf_check = ptr_used_leaf.get_value::<u8>();
 
         ensure!(
-            used_leaf_check != 0u8,
+            used_leaf_check == 0u8,
             "MERKLE DISTRIBUTOR: This leaf has already been used to claim"
         );
 
@@ -253,24 +271,29 @@ impl MerkleDistributor {
             .clone()
             .script_pubkey;
 
-        let tx_address = Address::from_script(&caller_script_pub_key, DEPLOYMENT_NETWORK)?;
+        let params = self.get_params_value()?;
+
+        let tx_address =
+            Address::from_script(&caller_script_pub_key, Network::from(params.network))?;
 
         ensure!(
             tx_address.to_string() == leaf.address,
             "MERKLE DISTRIBUTOR: vout #0 doesnt contain the address in merkle proof"
         );
 
-        let params_bytes = {
-            let ptr = self.get_merkle_root_pointer();
-            (*ptr.get()).clone()
-        };
+        ptr_used_leaf.set_value(1u8);
 
-        let params = decode_from_vec!(params_bytes, SchemaInitializeMerkleDistributorParameters)
-            .context(
-                "MERKLE DISTRIBUTOR: failed to decode initialization params when running claim",
-            )?;
+        let mut ptr_claimed = self.get_claimed_pointer();
+        let claimed = ptr_claimed
+            .get_value::<u128>()
+            .checked_add(leaf.amount)
+            .context("MERKLE DISTRIBUTOR: claimed total overflow")?;
+        ensure!(
+            claimed <= params.amount,
+            "MERKLE DISTRIBUTOR: claims would exceed the distributed amount"
+        );
+        ptr_claimed.set_value(claimed);
 
-        ptr_used_leaf.set_value(1u8);
         response.alkanes.0.push(AlkaneTransfer {
             id: params.alkane_id.into(),
             value: leaf.amount,
@@ -278,6 +301,93 @@ impl MerkleDistributor {
 
         Ok(response)
     }
+
+    //Once block_end has passed, sends whatever was not claimed to the beneficiary set at initialization.
+    //Claims are closed by then, so a single sweep empties the distributor
+    fn sweep(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = self.get_params_value()?;
+
+        let height: u128 = self.height().into();
+        ensure!(
+            height > params.block_end,
+            "MERKLE DISTRIBUTOR: claims are open until block {}",
+            params.block_end
+        );
+        ensure!(
+            self.get_sweep_value()?.is_none(),
+            "MERKLE DISTRIBUTOR: already swept"
+        );
+
+        match params.sweep_beneficiary {
+            SchemaSweepBeneficiary::Contract(beneficiary) => ensure!(
+                ctx.caller == beneficiary.into(),
+                "MERKLE DISTRIBUTOR: only {}:{} can sweep",
+                beneficiary.block,
+                beneficiary.tx
+            ),
+            SchemaSweepBeneficiary::AuthAlkane(auth) => ensure!(
+                response
+                    .alkanes
+                    .0
+                    .iter()
+                    .any(|transfer| transfer.id == auth.into() && transfer.value > 0),
+                "MERKLE DISTRIBUTOR: sweeping needs auth alkane {}:{}",
+                auth.block,
+                auth.tx
+            ),
+            SchemaSweepBeneficiary::Burn => {}
+        }
+
+        let amount = params
+            .amount
+            .checked_sub(self.get_claimed_pointer().get_value::<u128>())
+            .context("MERKLE DISTRIBUTOR: claimed more than the distributed amount")?;
+
+        ensure!(amount > 0, "MERKLE DISTRIBUTOR: nothing left to sweep");
+
+        if params.sweep_beneficiary != SchemaSweepBeneficiary::Burn {
+            response.alkanes.0.push(AlkaneTransfer {
+                id: params.alkane_id.into(),
+                value: amount,
+            });
+        }
+
+        self.get_sweep_pointer()
+            .set(Arc::new(borsh::to_vec(&SchemaSweepRecord {
+                amount,
+                block: self.height(),
+            })?));
+
+        response.data = borsh::to_vec(&amount)?;
+
+        Ok(response)
+    }
+
+    fn get_claim_totals(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = self.get_params_value()?;
+
+        let claimed = self.get_claimed_pointer().get_value::<u128>();
+        let sweep = self.get_sweep_value()?;
+        let swept = sweep.as_ref().map_or(0, |record| record.amount);
+        let height: u128 = self.height().into();
+
+        response.data = borsh::to_vec(&SchemaClaimTotals {
+            amount: params.amount,
+            claimed,
+            swept,
+            remaining: params.amount.saturating_sub(claimed).saturating_sub(swept),
+            block_end: params.block_end,
+            claim_window_open: height <= params.block_end,
+            sweep_beneficiary: params.sweep_beneficiary,
+            sweep,
+        })?;
+
+        Ok(response)
+    }
 }
 
 impl AlkaneResponder for MerkleDistributor {}
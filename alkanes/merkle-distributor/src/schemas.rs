@@ -1,4 +1,5 @@
 use alkanes_support::id::AlkaneId;
+use bitcoin::Network;
 use borsh::{BorshDeserialize, BorshSerialize};
 
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
@@ -13,6 +14,53 @@ pub struct SchemaInitializeMerkleDistributorParameters {
     pub alkane_id: SchemaAlkaneId,
     pub amount: u128,
     pub block_end: u128,
+    pub network: SchemaNetwork, //used to render vout #0 as an address when checking claims
+    pub sweep_beneficiary: SchemaSweepBeneficiary,
+}
+
+//Who gets what is left once block_end has passed
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
+pub enum SchemaSweepBeneficiary {
+    Contract(SchemaAlkaneId), //only this contract can Sweep and the balance is sent back to it, eg Tortilla
+    AuthAlkane(SchemaAlkaneId), //whoever sends this alkane in gets the balance, eg a treasury auth token
+    Burn, //anyone can Sweep, the balance stays in the distributor where no opcode can move it
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaSweepRecord {
+    pub amount: u128,
+    pub block: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaClaimTotals {
+    pub amount: u128,
+    pub claimed: u128,
+    pub swept: u128,
+    pub remaining: u128,
+    pub block_end: u128,
+    pub claim_window_open: bool,
+    pub sweep_beneficiary: SchemaSweepBeneficiary,
+    pub sweep: Option<SchemaSweepRecord>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
+pub enum SchemaNetwork {
+    Regtest,
+    Mainnet,
+    Testnet,
+    Signet,
+}
+
+impl From<SchemaNetwork> for Network {
+    fn from(value: SchemaNetwork) -> Self {
+        match value {
+            SchemaNetwork::Regtest => Network::Regtest,
+            SchemaNetwork::Mainnet => Network::Bitcoin,
+            SchemaNetwork::Testnet => Network::Testnet,
+            SchemaNetwork::Signet => Network::Signet,
+        }
+    }
 }
 impl From<SchemaAlkaneId> for AlkaneId {
     fn from(value: SchemaAlkaneId) -> Self {
@@ -1,6 +1,7 @@
 use crate::MerkleDistributor;
 use alkanes_runtime::runtime::AlkaneResponder;
 use alkanes_support::context::Context;
+use alkanes_support::id::AlkaneId;
 use alkanes_support::witness::find_witness_payload;
 use anyhow::{anyhow, Result};
 use bitcoin::Transaction;
@@ -88,7 +89,10 @@ impl MerkleDistributor {
             .get(pm_index)
             .ok_or_else(|| anyhow!("no protostone message at computed index"))?;
 
-        if !message.edicts.is_empty() {
+        //A contract proxying the claim (eg Tortilla's ClaimTortillaAirdrop) receives the airdrop itself, so
+        //edicts cannot redirect it and may be how that contract's own inputs are sent in. Direct claims
+        //still refuse them
+        if ctx.caller == AlkaneId::default() && !message.edicts.is_empty() {
             return Err(anyhow!("protostone message must have zero edicts"));
         }
 
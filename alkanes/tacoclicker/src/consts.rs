@@ -1,5 +1,7 @@
-use anyhow::{anyhow, Result};
-use bitcoin::Network;
+use crate::game::schemas::SchemaEmissionSchedule;
+use crate::schemas::{
+    SchemaFundingRecipient, SchemaNetwork, SchemaRegistrationPolicy, SchemaTacoClickerParams,
+};
 //1.5m
 pub const TORTILLA_AIRDROP_PREMINE: u128 = 150_000_000_000_000;
 
@@ -23,31 +25,96 @@ pub const MERKLE_ROOTS: MerkleRoots = MerkleRoots {
 
 pub const TORTILLA_CLAIM_WINDOW: u64 = 1440_u64;
 
+//Merkle distributor opcodes Tortilla calls on behalf of claimants and the admin
+pub const MERKLE_DISTRIBUTOR_CLAIM_OPCODE: u128 = 106;
+pub const MERKLE_DISTRIBUTOR_SWEEP_OPCODE: u128 = 108;
+
+//Defaults for the economic parameters below. They are written to storage at initialize and can be changed by the admin afterwards
+
 //15,000 TORTILLA per block with precision of 8
 pub const TORTILLA_PER_BLOCK: u128 = 1_500_000_000_000;
 
+//Emission halves roughly once a year (144 blocks * 365), capping total emission at 2 * TORTILLA_PER_BLOCK * interval
+pub const TORTILLA_HALVING_INTERVAL: u128 = 52_560;
+
 //every 144 blocks, someone will win 216,000 tortilla... 10% of the ENTIRE DAILY tortilla production of the game.
 pub const SALSA_BLOCK_REWARD: u128 = 21_600_000_000_000;
 
+//Share of the last purchase price refunded when an upgrade is sold back, in basis points
+pub const SELL_REFUND_BPS: u16 = 5_000;
+pub const BPS_DENOMINATOR: u128 = 10_000;
+
+//One time TORTILLA bonus credited to the referrer's unclaimed balance when a taqueria registers with a referral
+//5,000 TORTILLA, kept well below what the registration fee buys so self referring doesnt pay
+pub const REFERRAL_BONUS: u128 = 500_000_000_000;
+
+//Every referral bonus ever paid comes out of this fixed pool, 2,100,000 TORTILLA. Once it is used up
+//referrals are still recorded but pay nothing
+pub const REFERRAL_POOL: u128 = 210_000_000_000_000;
+
+//TORTILLA paid to RenameTaqueria, 1,000 TORTILLA
+pub const RENAME_PRICE: u128 = 100_000_000_000;
+
 //Amount people must pay to funding address to register
 pub const FUNDING_PRICE_SATS: u64 = 21_000;
 
-//My address for the monis
-pub const FUNDING_ADDRESS: &str =
+//Added to the registration price for every taqueria already registered. Off by default
+pub const FUNDING_PRICE_INCREMENT_SATS: u64 = 0;
+
+//Regtest address every deployment paid before the funding address was passed to initialize. Only the
+//params fallback for those deployments still reads it
+pub const LEGACY_FUNDING_ADDRESS: &str =
     "bcrt1pluksgqq4kf0kwu3unj00p4mla3xk7tq5ay49wnewt8eydmq22mhsn4qdaw";
 
+//Name of the 1/1 auth alkane minted to the deployer. Whoever holds it is the admin
+pub const ADMIN_TOKEN_NAME: &str = "TORTILLA AUTH";
+pub const ADMIN_TOKEN_SYMBOL: &str = "TORTILLA AUTH";
+
+//Max entries returned by paginated views
+pub const MAX_PAGE_SIZE: u32 = 100;
+
 //Constants so initialize doesnt need to be supplied with anything
 pub const TOKEN_NAME: &str = "TORTILLA";
 pub const TOKEN_SYMBOL: &str = "TORTILLA";
 
-pub const DEPLOYMENT_NETWORK: Network = Network::Regtest;
+//Only mainnet has a real airdrop snapshot, every test network shares the regtest root
+pub fn get_merkle_root_for_network(network: SchemaNetwork) -> [u8; 32] {
+    match network {
+        SchemaNetwork::Mainnet => MERKLE_ROOTS.mainnet,
+        SchemaNetwork::Regtest | SchemaNetwork::Testnet | SchemaNetwork::Signet => {
+            MERKLE_ROOTS.regtest
+        }
+    }
+}
+
+//Deployments from before params lived in storage emitted a flat rate. Halvings stay off for them, a
+//schedule counted from block 0 would halve what they already emitted, until the admin sets one
+pub fn legacy_params() -> SchemaTacoClickerParams {
+    let mut params = default_params(LEGACY_FUNDING_ADDRESS);
+    params.emission_schedule.halving_interval = 0;
+    params
+}
 
-pub fn get_merkle_root_from_id(id: u8) -> Result<[u8; 32]> {
-    match id {
-        0 => Ok(MERKLE_ROOTS.regtest),
-        1 => Ok(MERKLE_ROOTS.mainnet),
-        _ => Err(anyhow!(
-            "Invalid network ID: must be 0 (regtest) or 1 (mainnet)"
-        )),
+//initialize moves start_block to the deployment height and passes the funding address it was given
+pub fn default_params(funding_address: &str) -> SchemaTacoClickerParams {
+    SchemaTacoClickerParams {
+        emission_schedule: SchemaEmissionSchedule {
+            start_block: 0u128,
+            initial_tortilla_per_block: TORTILLA_PER_BLOCK,
+            halving_interval: TORTILLA_HALVING_INTERVAL,
+        },
+        salsa_block_reward: SALSA_BLOCK_REWARD,
+        sell_refund_bps: SELL_REFUND_BPS,
+        referral_bonus: REFERRAL_BONUS,
+        rename_price: RENAME_PRICE,
+        registration: SchemaRegistrationPolicy {
+            recipients: vec![SchemaFundingRecipient {
+                address: funding_address.to_string(),
+                share_bps: BPS_DENOMINATOR as u16,
+            }],
+            base_price_sats: FUNDING_PRICE_SATS,
+            price_increment_sats: FUNDING_PRICE_INCREMENT_SATS,
+            alkane_price: None,
+        },
     }
 }
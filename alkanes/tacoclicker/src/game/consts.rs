@@ -3,65 +3,120 @@ pub struct UpgradeSheetPriceEntry {
     pub name: &'static str,
     pub base_cost: u128,
     pub weight: u128,
+    pub price_growth_numerator: u128,
+    pub price_growth_denominator: u128,
 }
-pub struct UpgradesSheetTable {
-    pub taquero: UpgradeSheetPriceEntry,
-    pub salsa_bar: UpgradeSheetPriceEntry,
-    pub tortilla_tree: UpgradeSheetPriceEntry,
-    pub tortilla_factory: UpgradeSheetPriceEntry,
-    pub taco_submarine: UpgradeSheetPriceEntry,
-    pub taco_pyramid: UpgradeSheetPriceEntry,
-    pub tortilla_spaceship: UpgradeSheetPriceEntry,
-    pub satoshi_tacomoto: UpgradeSheetPriceEntry,
-}
 
-pub const UPGRADES: UpgradesSheetTable = UpgradesSheetTable {
-    taquero: UpgradeSheetPriceEntry {
+//Bumped when the stored SchemaGlobalEmissionState layout changes. 0 is the unscaled legacy layout
+pub const GLOBAL_EMISSION_STATE_VERSION: u8 = 1;
+
+//Cap on units per BuyUpgrade call, keeps the pricing loop bounded for 1x growth upgrades
+pub const MAX_UPGRADES_PER_PURCHASE: u32 = 100;
+
+//Most recent bets kept per taqueria. Older entries are overwritten, the running totals keep counting them
+pub const BET_HISTORY_SIZE: u64 = 100;
+
+//Leading zero bits a PoC hash needs before any retarget, same as the old 0x00 first byte rule
+pub const DEFAULT_POC_DIFFICULTY_BITS: u8 = 8;
+pub const MIN_POC_DIFFICULTY_BITS: u8 = 8;
+pub const MAX_POC_DIFFICULTY_BITS: u8 = 64;
+
+//PoC difficulty is retargeted once per window towards POC_TARGET_PER_INTERVAL successful clicks
+pub const POC_RETARGET_INTERVAL: u128 = 144;
+pub const POC_TARGET_PER_INTERVAL: u64 = 1_440;
+
+//Cap on taquerias created by one RegisterMany call, each one is a clone call into the factory
+pub const MAX_REGISTRATIONS_PER_CALL: u32 = 25;
+
+//Name and symbol every taqueria gets unless register is given its own
+pub const DEFAULT_TAQUERIA_NAME: &str = "TAQUERIA";
+pub const DEFAULT_TAQUERIA_SYMBOL: &str = "TAQUERIA";
+
+pub const MAX_TAQUERIA_NAME_LENGTH: usize = 32;
+pub const MAX_TAQUERIA_SYMBOL_LENGTH: usize = 10;
+
+//Taquerias tracked by the on-chain leaderboard
+pub const LEADERBOARD_SIZE: usize = 100;
+
+//Fixed point scale for leaderboard weight shares, 100_000_000 == 100%
+pub const SHARE_SCALE: u128 = 100_000_000;
+
+//Blocks per salsa round. Round n covers blocks [n * SALSA_ROUND_BLOCKS, (n + 1) * SALSA_ROUND_BLOCKS)
+pub const SALSA_ROUND_BLOCKS: u128 = 144;
+
+//CommitBet settles on the hash of the block this many blocks after the commit
+pub const BET_COMMIT_DELAY: u128 = 2;
+
+//Blocks after the target block during which a commit can still be revealed. Later reveals forfeit the stake
+pub const BET_REVEAL_WINDOW: u128 = 144;
+
+//Upgrade every new taqueria starts with (one free unit)
+pub const STARTER_UPGRADE_ID: u8 = 0;
+
+//Seed for the on-chain upgrade catalogue. Only read at initialize (or when a legacy deployment has no catalogue stored yet)
+pub const DEFAULT_UPGRADES: [UpgradeSheetPriceEntry; 8] = [
+    UpgradeSheetPriceEntry {
         id: 0,
         name: "Taquero",
         base_cost: 10_000_000_000u128,
         weight: 1u128,
+        price_growth_numerator: 3u128,
+        price_growth_denominator: 2u128,
     },
-    salsa_bar: UpgradeSheetPriceEntry {
+    UpgradeSheetPriceEntry {
         id: 1,
         name: "Salsa Bar",
         base_cost: 300_000_000_000u128,
         weight: 20u128,
+        price_growth_numerator: 3u128,
+        price_growth_denominator: 2u128,
     },
-    tortilla_tree: UpgradeSheetPriceEntry {
+    UpgradeSheetPriceEntry {
         id: 2,
         name: "Tortilla Tree",
         base_cost: 2_500_000_000_000u128,
         weight: 300u128,
+        price_growth_numerator: 3u128,
+        price_growth_denominator: 2u128,
     },
-    tortilla_factory: UpgradeSheetPriceEntry {
+    UpgradeSheetPriceEntry {
         id: 3,
         name: "Tortilla Factory",
         base_cost: 15_000_000_000_000u128,
         weight: 2_400u128,
+        price_growth_numerator: 3u128,
+        price_growth_denominator: 2u128,
     },
-    taco_submarine: UpgradeSheetPriceEntry {
+    UpgradeSheetPriceEntry {
         id: 4,
         name: "Taco Bank",
         base_cost: 115_000_000_000_000u128,
         weight: 15_000u128,
+        price_growth_numerator: 3u128,
+        price_growth_denominator: 2u128,
     },
-    taco_pyramid: UpgradeSheetPriceEntry {
+    UpgradeSheetPriceEntry {
         id: 5,
         name: "Taco Pyramid",
         base_cost: 500_000_000_000_000u128,
         weight: 60_000u128,
+        price_growth_numerator: 3u128,
+        price_growth_denominator: 2u128,
     },
-    tortilla_spaceship: UpgradeSheetPriceEntry {
+    UpgradeSheetPriceEntry {
         id: 6,
         name: "Tortilla Spaceship",
         base_cost: 2_000_000_000_000_000u128,
         weight: 200_000u128,
+        price_growth_numerator: 3u128,
+        price_growth_denominator: 2u128,
     },
-    satoshi_tacomoto: UpgradeSheetPriceEntry {
+    UpgradeSheetPriceEntry {
         id: 7,
         name: "Satoshi Tacomoto",
         base_cost: 10_000_000_000_000_000u128,
         weight: 1_000_000u128,
+        price_growth_numerator: 3u128,
+        price_growth_denominator: 2u128,
     },
-};
+];
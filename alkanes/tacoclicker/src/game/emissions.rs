@@ -0,0 +1,329 @@
+use crate::game::schemas::{
+    SchemaEmissionSchedule, SchemaGlobalEmissionState, SchemaLegacyGlobalEmissionState,
+};
+use anyhow::{ensure, Context, Result};
+
+//Fixed point scale of acc_reward_per_weight
+pub const ACC_PRECISION: u128 = 1_000_000_000_000_000_000;
+
+//After this many halvings any u128 rate has been shifted down to zero
+const MAX_HALVINGS: u128 = 128;
+
+//Blocks before start_block count as the first epoch
+pub fn emission_epoch(schedule: &SchemaEmissionSchedule, height: u128) -> u128 {
+    if schedule.halving_interval == 0 || height < schedule.start_block {
+        return 0;
+    }
+
+    (height - schedule.start_block) / schedule.halving_interval
+}
+
+pub fn emission_rate_at(schedule: &SchemaEmissionSchedule, height: u128) -> u128 {
+    let epoch = emission_epoch(schedule, height);
+
+    if epoch >= MAX_HALVINGS {
+        return 0;
+    }
+
+    schedule.initial_tortilla_per_block >> epoch
+}
+
+//First block of the epoch after the one `height` falls in. None once the rate can never change again
+pub fn next_emission_change(schedule: &SchemaEmissionSchedule, height: u128) -> Option<u128> {
+    if schedule.halving_interval == 0 || emission_rate_at(schedule, height) == 0 {
+        return None;
+    }
+
+    emission_epoch(schedule, height)
+        .checked_add(1)?
+        .checked_mul(schedule.halving_interval)?
+        .checked_add(schedule.start_block)
+}
+
+//Total emitted over the blocks [from, to). Walks one epoch at a time so a gap that crosses
+//a halving is integrated at the right rate on each side of it
+pub fn emission_between(schedule: &SchemaEmissionSchedule, from: u128, to: u128) -> Result<u128> {
+    let mut total = 0u128;
+    let mut cursor = from;
+
+    while cursor < to {
+        let rate = emission_rate_at(schedule, cursor);
+
+        if rate == 0 {
+            break;
+        }
+
+        let segment_end = next_emission_change(schedule, cursor).map_or(to, |block| block.min(to));
+
+        total = (segment_end - cursor)
+            .checked_mul(rate)
+            .and_then(|emitted| total.checked_add(emitted))
+            .context("TORTILLA: emission overflow")?;
+
+        cursor = segment_end;
+    }
+
+    Ok(total)
+}
+
+//Brings the accumulator forward to now_block. The share of emission * ACC_PRECISION that doesnt
+//divide evenly by total_weight is carried in acc_remainder for the next accrual instead of being dropped
+pub fn accrue_global_emission(
+    state: &mut SchemaGlobalEmissionState,
+    schedule: &SchemaEmissionSchedule,
+    now_block: u128,
+) -> Result<()> {
+    ensure!(
+        now_block >= state.last_updated_block,
+        "TORTILLA: block underflow"
+    );
+
+    let emission = emission_between(schedule, state.last_updated_block, now_block)?;
+
+    // emission * P / W == (emission / W) * P + (emission % W) * P / W
+    if let Some(whole_per_weight) = emission.checked_div(state.total_weight) {
+        let whole = whole_per_weight
+            .checked_mul(ACC_PRECISION)
+            .context("TORTILLA: accumulator overflow")?;
+        let scaled_fraction = (emission % state.total_weight)
+            .checked_mul(ACC_PRECISION)
+            .and_then(|scaled| scaled.checked_add(state.acc_remainder))
+            .context("TORTILLA: accumulator overflow")?;
+
+        state.acc_reward_per_weight = state
+            .acc_reward_per_weight
+            .checked_add(whole)
+            .and_then(|acc| acc.checked_add(scaled_fraction / state.total_weight))
+            .context("TORTILLA: accumulator overflow")?;
+        state.acc_remainder = scaled_fraction % state.total_weight;
+        state.total_emitted = state.total_emitted.saturating_add(emission);
+    } else {
+        //Nobody to credit, this is never minted
+        state.unallocated_emission = state.unallocated_emission.saturating_add(emission);
+    }
+
+    state.last_updated_block = now_block;
+
+    Ok(())
+}
+
+//The unscaled accumulator times ACC_PRECISION, so accrued_reward of a legacy debt stays the same number
+pub fn migrate_legacy_global_emission_state(
+    legacy: &SchemaLegacyGlobalEmissionState,
+) -> Result<SchemaGlobalEmissionState> {
+    Ok(SchemaGlobalEmissionState {
+        total_weight: legacy.total_weight,
+        acc_reward_per_weight: legacy
+            .acc_reward_per_weight
+            .checked_mul(ACC_PRECISION)
+            .context("TORTILLA: overflow migrating accumulator")?,
+        last_updated_block: legacy.last_updated_block,
+        acc_remainder: 0u128,
+        total_emitted: 0u128,
+        unallocated_emission: 0u128,
+    })
+}
+
+//weight * acc / ACC_PRECISION in whole tortilla units. Split on the precision so a large weight
+//times a large accumulator cant overflow before the division
+pub fn accrued_reward(weight: u128, acc_reward_per_weight: u128) -> Result<u128> {
+    let whole = weight
+        .checked_mul(acc_reward_per_weight / ACC_PRECISION)
+        .context("TORTILLA: mul overflow in accrued reward")?;
+    let fraction = weight
+        .checked_mul(acc_reward_per_weight % ACC_PRECISION)
+        .context("TORTILLA: mul overflow in accrued reward")?
+        / ACC_PRECISION;
+
+    whole
+        .checked_add(fraction)
+        .context("TORTILLA: add overflow in accrued reward")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::legacy_params;
+    use borsh::BorshDeserialize;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    const START: u128 = 100;
+    const INTERVAL: u128 = 10;
+
+    fn schedule(initial_tortilla_per_block: u128) -> SchemaEmissionSchedule {
+        SchemaEmissionSchedule {
+            start_block: START,
+            initial_tortilla_per_block,
+            halving_interval: INTERVAL,
+        }
+    }
+
+    //One block at a time, halving by hand
+    fn brute_force(schedule: &SchemaEmissionSchedule, from: u128, to: u128) -> u128 {
+        (from..to)
+            .map(|height| {
+                let halvings = match schedule.halving_interval {
+                    0 => 0,
+                    interval => height.saturating_sub(schedule.start_block) / interval,
+                };
+                if halvings >= 128 {
+                    0
+                } else {
+                    schedule.initial_tortilla_per_block >> halvings
+                }
+            })
+            .sum()
+    }
+
+    fn assert_matches_brute_force(schedule: &SchemaEmissionSchedule, from: u128, to: u128) {
+        assert_eq!(
+            emission_between(schedule, from, to).unwrap(),
+            brute_force(schedule, from, to),
+            "blocks [{from}, {to})"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn gap_inside_one_epoch() {
+        let schedule = schedule(1_000);
+        assert_matches_brute_force(&schedule, 0, START);
+        assert_matches_brute_force(&schedule, START + 1, START + INTERVAL - 1);
+        assert_matches_brute_force(&schedule, START + 2 * INTERVAL, START + 3 * INTERVAL);
+    }
+
+    #[wasm_bindgen_test]
+    fn gap_across_one_and_several_halvings() {
+        let schedule = schedule(1_000);
+        assert_matches_brute_force(&schedule, START + 5, START + INTERVAL + 5);
+        assert_matches_brute_force(&schedule, START - 3, START + 3);
+        assert_matches_brute_force(&schedule, START + 5, START + 4 * INTERVAL + 7);
+        assert_matches_brute_force(&schedule, 0, START + 7 * INTERVAL);
+    }
+
+    #[wasm_bindgen_test]
+    fn empty_gap_emits_nothing() {
+        let schedule = schedule(1_000);
+        for block in [0, START, START + INTERVAL + 3] {
+            assert_eq!(emission_between(&schedule, block, block).unwrap(), 0);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn rate_runs_out_after_the_last_halving() {
+        //1_000 >> 10 == 0, so nothing is emitted from the tenth epoch on
+        let schedule = schedule(1_000);
+        let exhausted = START + 10 * INTERVAL;
+
+        assert_eq!(emission_rate_at(&schedule, exhausted), 0);
+        assert_eq!(next_emission_change(&schedule, exhausted), None);
+        assert_eq!(
+            next_emission_change(&schedule, exhausted - 1),
+            Some(exhausted)
+        );
+        assert_eq!(
+            emission_between(&schedule, exhausted, exhausted + 1_000).unwrap(),
+            0
+        );
+        assert_matches_brute_force(&schedule, START + 3, exhausted + 1_000);
+
+        //u128::MAX only runs out after MAX_HALVINGS epochs
+        let schedule = self::schedule(u128::MAX);
+        let last = START + (MAX_HALVINGS - 1) * INTERVAL;
+        assert_eq!(emission_rate_at(&schedule, last), 1);
+        assert_eq!(emission_rate_at(&schedule, last + INTERVAL), 0);
+        assert_eq!(next_emission_change(&schedule, last + INTERVAL), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn legacy_params_emit_at_a_flat_rate() {
+        let schedule = legacy_params().emission_schedule;
+        let blocks = 1_000_000u128;
+
+        assert_eq!(next_emission_change(&schedule, 0), None);
+        assert_eq!(
+            emission_between(&schedule, 0, blocks).unwrap(),
+            blocks * schedule.initial_tortilla_per_block
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn accrual_across_halvings_credits_the_brute_force_emission() {
+        let schedule = schedule(1_000);
+        let mut state = SchemaGlobalEmissionState {
+            total_weight: 1,
+            acc_reward_per_weight: 0,
+            last_updated_block: START + 5,
+            acc_remainder: 0,
+            total_emitted: 0,
+            unallocated_emission: 0,
+        };
+        let now_block = START + 4 * INTERVAL + 7;
+
+        accrue_global_emission(&mut state, &schedule, now_block).unwrap();
+
+        let expected = brute_force(&schedule, START + 5, now_block);
+        assert_eq!(state.total_emitted, expected);
+        assert_eq!(
+            accrued_reward(1, state.acc_reward_per_weight).unwrap(),
+            expected
+        );
+        assert_eq!(state.last_updated_block, now_block);
+    }
+
+    #[wasm_bindgen_test]
+    fn carried_remainder_loses_no_dust() {
+        //ACC_PRECISION is not a multiple of 3, so every accrual leaves a remainder to carry
+        let schedule = SchemaEmissionSchedule {
+            start_block: 0,
+            initial_tortilla_per_block: 1,
+            halving_interval: 0,
+        };
+        let weights = [1u128, 1, 1];
+        let mut state = SchemaGlobalEmissionState {
+            total_weight: weights.iter().sum(),
+            acc_reward_per_weight: 0,
+            last_updated_block: 0,
+            acc_remainder: 0,
+            total_emitted: 0,
+            unallocated_emission: 0,
+        };
+
+        for block in 1..=3_000u128 {
+            accrue_global_emission(&mut state, &schedule, block).unwrap();
+            assert_eq!(
+                state.acc_reward_per_weight * state.total_weight + state.acc_remainder,
+                state.total_emitted * ACC_PRECISION
+            );
+        }
+
+        let paid: u128 = weights
+            .iter()
+            .map(|weight| accrued_reward(*weight, state.acc_reward_per_weight).unwrap())
+            .sum();
+        assert_eq!(state.total_emitted, 3_000);
+        assert_eq!(paid, state.total_emitted);
+    }
+
+    #[wasm_bindgen_test]
+    fn legacy_state_keeps_its_accrued_reward() {
+        let bytes = borsh::to_vec(&SchemaLegacyGlobalEmissionState {
+            total_weight: 40,
+            acc_reward_per_weight: 123_456_789,
+            last_updated_block: 840_000,
+        })
+        .unwrap();
+        let legacy = SchemaLegacyGlobalEmissionState::try_from_slice(&bytes).unwrap();
+        let state = migrate_legacy_global_emission_state(&legacy).unwrap();
+
+        //Legacy debts were weight * unscaled acc, the migrated accumulator has to give the same amount
+        for weight in [1u128, 7, 40] {
+            assert_eq!(
+                accrued_reward(weight, state.acc_reward_per_weight).unwrap(),
+                weight * legacy.acc_reward_per_weight
+            );
+        }
+        assert_eq!(state.total_weight, legacy.total_weight);
+        assert_eq!(state.last_updated_block, legacy.last_updated_block);
+        assert_eq!(state.acc_remainder, 0);
+    }
+}
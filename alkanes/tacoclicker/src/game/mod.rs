@@ -1,5 +1,8 @@
 pub mod consts;
+pub mod emissions;
 pub mod multipliers;
+pub mod poc;
+pub mod registration;
 pub mod schemas;
 pub mod storage;
 pub mod utils;
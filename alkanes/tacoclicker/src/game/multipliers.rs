@@ -1,4 +1,6 @@
-use anyhow::{Context, Result};
+use crate::game::consts::BET_REVEAL_WINDOW;
+use crate::game::schemas::{SchemaBetCommit, SchemaPreviewBetResponse, SchemaRevealBetResponse};
+use anyhow::{ensure, Context, Result};
 use sha2::{Digest, Sha256};
 
 const TWO64: u128 = 1u128 << 64;
@@ -6,6 +8,10 @@ const SCALE: u128 = 100_000_000;
 const CAP: u128 = 10_000;
 const CAP_SCALED: u128 = CAP * SCALE;
 
+//Fixed point used by ln_ratio
+const LN_ONE: u128 = 1_000_000_000_000_000_000;
+const LN_2: u128 = 693_147_180_559_945_309;
+
 pub fn multiplier_from_seed(seed: &[u8]) -> Result<u128> {
     // 1. SHA‑256(seed)
     let digest = Sha256::digest(seed);
@@ -15,25 +21,318 @@ pub fn multiplier_from_seed(seed: &[u8]) -> Result<u128> {
             .context("TORTILLA: failed to unwrap multiplier into u128")?,
     ) as u128; // 0 ≤ x < 2⁶⁴
 
+    Ok(multiplier_in_space(TWO64, x))
+}
+
+//min(space * 1e8 / (space - x), CAP_SCALED) for x in [0, space). Seeds use space = 2^64, tests use
+//small spaces they can enumerate
+fn multiplier_in_space(space: u128, x: u128) -> u128 {
     if x == 0 {
-        return Ok(SCALE); // exactly 1.00000000
+        return SCALE; // exactly 1.00000000
     }
 
-    let denom = TWO64 - x; // never 0
-    let num = TWO64 * SCALE; // fits in u128 (≈1.8×10³⁰)
+    let denom = space - x; // never 0
+    let num = space * SCALE; // fits in u128 (≈1.8×10³⁰)
     let m = num / denom; // integer division
 
-    Ok(m.min(CAP_SCALED))
+    m.min(CAP_SCALED)
 }
 
 pub fn apply_multiplier(value: u128, seed: &[u8]) -> Result<u128> {
     let m = multiplier_from_seed(seed)?; // already scaled by 1e8
 
+    Ok(scale_by_multiplier(value, m))
+}
+
+//Outcome of a commit, the stake is lost past BET_REVEAL_WINDOW. A target hash that was never
+//recorded also loses, otherwise the bettor could skip recording a losing hash and get the stake back
+pub fn settle_bet(
+    commit: &SchemaBetCommit,
+    target_hash: Option<&[u8]>,
+    now_block: u128,
+) -> Result<SchemaRevealBetResponse> {
+    let expired = now_block > commit.target_block.saturating_add(BET_REVEAL_WINDOW);
+
+    let hash = match target_hash {
+        Some(hash) if !expired => hash,
+        _ => {
+            return Ok(SchemaRevealBetResponse {
+                won_amount: 0,
+                lost_amount: commit.locked_amount,
+                multiplier: 0,
+                expired,
+            })
+        }
+    };
+
+    let multiplier = multiplier_from_seed(hash)?;
+
+    if multiplier > commit.target_multiplier {
+        let new_amt = apply_multiplier(commit.locked_amount, hash)?;
+
+        Ok(SchemaRevealBetResponse {
+            won_amount: new_amt.saturating_sub(commit.locked_amount),
+            lost_amount: 0,
+            multiplier,
+            expired,
+        })
+    } else {
+        Ok(SchemaRevealBetResponse {
+            won_amount: 0,
+            lost_amount: commit.locked_amount,
+            multiplier,
+            expired,
+        })
+    }
+}
+
+//value * m / 1e8 without overflowing on large values
+fn scale_by_multiplier(value: u128, m: u128) -> u128 {
     let q = value / SCALE;
     let r = value % SCALE;
 
     let part1 = q.saturating_mul(m); // q ≤ 2¹⁰², m ≤ 1e12 => fits 128 but saturate for safety
     let part2 = r.saturating_mul(m) / SCALE; // r < SCALE so product < 1e20 < 2⁶⁷
 
-    Ok(part1.saturating_add(part2)) // final result in fixed‑point
+    part1.saturating_add(part2) // final result in fixed‑point
+}
+
+//ln(num / den) scaled by LN_ONE, for num >= den > 0
+fn ln_ratio(num: u128, den: u128) -> Result<u128> {
+    ensure!(den > 0 && num >= den, "TORTILLA: ln_ratio out of domain");
+
+    // r = num / den in LN_ONE fixed point. (num % den) < den <= 2^64 so the product fits
+    let mut r = (num / den)
+        .checked_mul(LN_ONE)
+        .and_then(|whole| whole.checked_add((num % den) * LN_ONE / den))
+        .context("TORTILLA: ln_ratio overflow")?;
+
+    // ln(r) = k * ln 2 + ln(r / 2^k) with r / 2^k in [1, 2)
+    let mut k = 0u128;
+    while r >= 2 * LN_ONE {
+        r >>= 1;
+        k += 1;
+    }
+
+    // ln(r) = 2 * atanh(z), z = (r - 1) / (r + 1) <= 1/3
+    let z = (r - LN_ONE) * LN_ONE / (r + LN_ONE);
+    let z2 = z * z / LN_ONE;
+
+    let mut term = z;
+    let mut series = 0u128;
+    let mut n = 0u128;
+    while term > 0 {
+        series += term / (2 * n + 1);
+        term = term * z2 / LN_ONE;
+        n += 1;
+    }
+
+    Ok(k * LN_2 + 2 * series)
+}
+
+/*
+    multiplier_from_seed maps x (first 8 bytes of the digest) to min(2^64 * 1e8 / (2^64 - x), CAP_SCALED), which
+    only grows with x. With u = 2^64 - x the bet wins when floor(N / u) > t, ie u <= N / (t + 1), so the count of
+    winning seeds is exact. Every u <= N / CAP_SCALED hits the cap, the remaining winners add up
+    sum N / u ~= N * ln(D / u_cap), which is off by well under one multiplier unit
+*/
+pub fn preview_bet(target_multiplier: u128, amount: u128) -> Result<SchemaPreviewBetResponse> {
+    preview_in_space(TWO64, target_multiplier, amount)
+}
+
+fn preview_in_space(
+    space: u128,
+    target_multiplier: u128,
+    amount: u128,
+) -> Result<SchemaPreviewBetResponse> {
+    if target_multiplier >= CAP_SCALED {
+        return Ok(SchemaPreviewBetResponse {
+            winning_seeds: 0,
+            win_probability: 0,
+            expected_multiplier: 0,
+            expected_payout: 0,
+            max_payout: 0,
+        });
+    }
+
+    let num = space * SCALE;
+    let winning_seeds = (num / (target_multiplier + 1)).min(space);
+    let capped_seeds = num / CAP_SCALED;
+
+    let capped_part = winning_seeds.min(capped_seeds) * CAP_SCALED / space;
+    let tail_part = if winning_seeds > capped_seeds {
+        SCALE * ln_ratio(winning_seeds, capped_seeds)? / LN_ONE
+    } else {
+        0
+    };
+    let expected_multiplier = capped_part + tail_part;
+
+    Ok(SchemaPreviewBetResponse {
+        winning_seeds,
+        win_probability: winning_seeds * SCALE / space,
+        expected_multiplier,
+        expected_payout: scale_by_multiplier(amount, expected_multiplier),
+        max_payout: scale_by_multiplier(amount, CAP_SCALED),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    const TARGET_BLOCK: u128 = 1_000;
+
+    fn commit(target_multiplier: u128) -> SchemaBetCommit {
+        SchemaBetCommit {
+            locked_amount: 5_000,
+            target_multiplier,
+            committed_block: TARGET_BLOCK - 1,
+            target_block: TARGET_BLOCK,
+        }
+    }
+
+    //First seed i.to_le_bytes() whose multiplier is on the requested side of target
+    fn seed(target_multiplier: u128, winning: bool) -> Vec<u8> {
+        (0u64..)
+            .map(|i| i.to_le_bytes().to_vec())
+            .find(|seed| (multiplier_from_seed(seed).unwrap() > target_multiplier) == winning)
+            .unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn losing_bet_cannot_be_turned_into_a_refund() {
+        let commit = commit(2 * SCALE);
+        let losing = seed(commit.target_multiplier, false);
+
+        let revealed = settle_bet(&commit, Some(&losing), TARGET_BLOCK + 1).unwrap();
+        let unrecorded = settle_bet(&commit, None, TARGET_BLOCK + 1).unwrap();
+
+        //Skipping the hash recording settles no better than revealing the losing hash
+        for outcome in [revealed, unrecorded] {
+            assert_eq!(outcome.won_amount, 0);
+            assert_eq!(outcome.lost_amount, commit.locked_amount);
+            assert!(!outcome.expired);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn winning_bet_pays_out_inside_the_reveal_window_only() {
+        let commit = commit(2 * SCALE);
+        let winning = seed(commit.target_multiplier, true);
+
+        let revealed =
+            settle_bet(&commit, Some(&winning), TARGET_BLOCK + BET_REVEAL_WINDOW).unwrap();
+        assert_eq!(revealed.lost_amount, 0);
+        assert_eq!(
+            revealed.won_amount,
+            apply_multiplier(commit.locked_amount, &winning).unwrap() - commit.locked_amount
+        );
+
+        let late = settle_bet(
+            &commit,
+            Some(&winning),
+            TARGET_BLOCK + BET_REVEAL_WINDOW + 1,
+        )
+        .unwrap();
+        assert!(late.expired);
+        assert_eq!(late.won_amount, 0);
+        assert_eq!(late.lost_amount, commit.locked_amount);
+    }
+
+    //Every x of a 2^bits space, counting winners and summing their multipliers
+    fn brute_force(bits: u32, target_multiplier: u128) -> (u128, u128) {
+        let space = 1u128 << bits;
+        (0..space)
+            .map(|x| multiplier_in_space(space, x))
+            .filter(|multiplier| *multiplier > target_multiplier)
+            .fold((0, 0), |(count, sum), multiplier| {
+                (count + 1, sum + multiplier)
+            })
+    }
+
+    const TARGETS: [u128; 7] = [
+        0,
+        SCALE / 2,
+        SCALE,
+        2 * SCALE,
+        3 * SCALE + 1,
+        100 * SCALE,
+        CAP_SCALED - 1,
+    ];
+
+    #[wasm_bindgen_test]
+    fn preview_counts_every_winning_seed() {
+        for bits in [20, 21] {
+            let space = 1u128 << bits;
+            for target in TARGETS {
+                let preview = preview_in_space(space, target, SCALE).unwrap();
+                let (winning, _) = brute_force(bits, target);
+
+                assert_eq!(preview.winning_seeds, winning, "2^{bits} target {target}");
+                assert_eq!(preview.win_probability, winning * SCALE / space);
+            }
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn preview_expected_multiplier_matches_the_hash_space() {
+        let bits = 22;
+        let space = 1u128 << bits;
+
+        //The tail is integrated from the cap boundary, which is off by at most one multiplier per
+        //seed there, CAP_SCALED / space on the average
+        let tolerance = CAP_SCALED / space + 1;
+
+        for target in TARGETS {
+            let preview = preview_in_space(space, target, SCALE).unwrap();
+            let (_, sum) = brute_force(bits, target);
+            let expected = sum / space;
+
+            assert!(
+                preview.expected_multiplier.abs_diff(expected) <= tolerance,
+                "target {target}: preview {} brute force {expected}",
+                preview.expected_multiplier
+            );
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn preview_winning_seeds_end_at_the_target_in_the_seed_space() {
+        for target in TARGETS {
+            let winning = preview_bet(target, SCALE).unwrap().winning_seeds;
+
+            //u = 2^64 - x counts down from the luckiest seed, the last winner beats target and the next doesnt
+            assert!(multiplier_in_space(TWO64, TWO64 - winning) > target);
+            if winning < TWO64 {
+                assert!(multiplier_in_space(TWO64, TWO64 - winning - 1) <= target);
+            }
+        }
+
+        assert_eq!(preview_bet(CAP_SCALED, SCALE).unwrap().winning_seeds, 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn ln_ratio_matches_reference_values() {
+        //ln(num / den) * 1e18, from the ends of what preview_bet asks for down to ln 1
+        let references: [(u128, u128, u128); 6] = [
+            (1, 1, 0),
+            (3, 2, 405_465_108_108_164_381),
+            (2, 1, LN_2),
+            (1_000_000_007, 1_000_000_000, 6_999_999_975),
+            (CAP, 1, 9_210_340_371_976_182_736),
+            (TWO64, TWO64 * SCALE / CAP_SCALED, 9_210_340_371_976_182_824),
+        ];
+
+        for (num, den, reference) in references {
+            let ln = ln_ratio(num, den).unwrap();
+            assert!(
+                ln.abs_diff(reference) <= 1_000_000,
+                "ln({num} / {den}) = {ln}, expected {reference}"
+            );
+        }
+
+        assert!(ln_ratio(1, 2).is_err());
+        assert!(ln_ratio(1, 0).is_err());
+    }
 }
@@ -0,0 +1,134 @@
+use crate::game::consts::{
+    DEFAULT_POC_DIFFICULTY_BITS, MAX_POC_DIFFICULTY_BITS, MIN_POC_DIFFICULTY_BITS,
+    POC_RETARGET_INTERVAL, POC_TARGET_PER_INTERVAL,
+};
+use crate::game::schemas::SchemaPocDifficultyState;
+use crate::schemas::SchemaAlkaneId;
+use anyhow::{ensure, Result};
+
+pub use tortilla_poc::{leading_zero_bits, meets_poc_difficulty, poc_hash};
+
+//SHA‑256(borsh id ‖ 16‑byte BE nonce ‖ previous hash), shared with the native miner in tortilla-poc.
+//Fails unless the hash has difficulty_bits leading zero bits
+pub fn proof_of_click_hash(
+    taqueria: &SchemaAlkaneId,
+    nonce_found_poc: u128,
+    last_poc_hash: &[u8],
+    difficulty_bits: u8,
+) -> Result<[u8; 32]> {
+    let new_hash = poc_hash(taqueria, nonce_found_poc, last_poc_hash)?;
+
+    ensure!(
+        meets_poc_difficulty(&new_hash, difficulty_bits),
+        "TORTILLA: new PoC hash needs {} leading zero bits",
+        difficulty_bits
+    );
+
+    Ok(new_hash)
+}
+
+pub fn poc_window_start(height: u128) -> u128 {
+    height - height % POC_RETARGET_INTERVAL
+}
+
+//State for deployments that never stored one, equivalent to the old fixed 0x00 first byte rule
+pub fn default_poc_difficulty(height: u128) -> SchemaPocDifficultyState {
+    SchemaPocDifficultyState {
+        difficulty_bits: DEFAULT_POC_DIFFICULTY_BITS,
+        window_start_block: poc_window_start(height),
+        window_poc_count: 0,
+        total_poc_count: 0,
+    }
+}
+
+//Closes every window that ended before now_block. Each bit doubles the work, so one step per window is
+//plenty: more than twice the target raises it, less than half lowers it. Empty windows keep easing the
+//difficulty until the floor, after which the rest are skipped in one go
+pub fn retarget_poc_difficulty(state: &mut SchemaPocDifficultyState, now_block: u128) {
+    while now_block
+        >= state
+            .window_start_block
+            .saturating_add(POC_RETARGET_INTERVAL)
+    {
+        let count = state.window_poc_count;
+
+        if count > POC_TARGET_PER_INTERVAL.saturating_mul(2) {
+            state.difficulty_bits = (state.difficulty_bits + 1).min(MAX_POC_DIFFICULTY_BITS);
+        } else if count.saturating_mul(2) < POC_TARGET_PER_INTERVAL {
+            state.difficulty_bits = state
+                .difficulty_bits
+                .saturating_sub(1)
+                .max(MIN_POC_DIFFICULTY_BITS);
+        }
+
+        state.window_poc_count = 0;
+
+        if count == 0 && state.difficulty_bits == MIN_POC_DIFFICULTY_BITS {
+            state.window_start_block = poc_window_start(now_block);
+            break;
+        }
+
+        state.window_start_block += POC_RETARGET_INTERVAL;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+    use tortilla_poc::miner::PocMiner;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn mine(
+        taqueria: SchemaAlkaneId,
+        last_poc_hash: &[u8],
+        difficulty_bits: u8,
+    ) -> (u128, [u8; 32]) {
+        let solution = PocMiner {
+            taqueria,
+            last_poc_hash: last_poc_hash.to_vec(),
+            difficulty_bits,
+            threads: 1,
+            start_nonce: 0,
+        }
+        .mine()
+        .unwrap()
+        .unwrap();
+
+        (solution.nonce, solution.hash)
+    }
+
+    #[wasm_bindgen_test]
+    fn mined_nonce_passes_proof_of_click() {
+        let taqueria = SchemaAlkaneId { block: 2, tx: 17 };
+
+        for last_poc_hash in [Vec::new(), Sha256::digest(b"previous click").to_vec()] {
+            let (nonce, hash) = mine(taqueria, &last_poc_hash, 8);
+
+            //The layout both sides must agree on, spelled out by hand
+            let mut expected = Sha256::new();
+            expected.update(2u32.to_le_bytes());
+            expected.update(17u64.to_le_bytes());
+            expected.update(nonce.to_be_bytes());
+            expected.update(&last_poc_hash);
+            assert_eq!(hash, <[u8; 32]>::from(expected.finalize()));
+
+            assert_eq!(
+                proof_of_click_hash(&taqueria, nonce, &last_poc_hash, 8).unwrap(),
+                hash
+            );
+
+            //The miner returns the first hit from nonce 0, so every earlier nonce is rejected
+            for earlier in 0..nonce {
+                assert!(proof_of_click_hash(&taqueria, earlier, &last_poc_hash, 8).is_err());
+            }
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn mined_nonce_is_bound_to_the_taqueria() {
+        let (nonce, _) = mine(SchemaAlkaneId { block: 2, tx: 17 }, &[], 12);
+
+        assert!(proof_of_click_hash(&SchemaAlkaneId { block: 2, tx: 18 }, nonce, &[], 12).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use crate::consts::BPS_DENOMINATOR;
+use crate::schemas::{
+    SchemaAlkaneRegistrationQuote, SchemaFundingRecipientQuote, SchemaNetwork,
+    SchemaRegistrationPolicy, SchemaRegistrationQuote,
+};
+use anyhow::{ensure, Context, Result};
+use bitcoin::{address::NetworkUnchecked, Address};
+
+//Sum of base + increment * (registered + i) for i in 0..count, ie the price of each taqueria
+//rises linearly with the number already registered
+fn bonding_curve_total(base: u128, increment: u128, registered: u128, count: u128) -> Option<u128> {
+    // count * registered + count * (count - 1) / 2 steps of increment
+    let steps = count
+        .checked_mul(registered)?
+        .checked_add(count.checked_mul(count.saturating_sub(1))? / 2)?;
+
+    count
+        .checked_mul(base)?
+        .checked_add(increment.checked_mul(steps)?)
+}
+
+//Payments are matched against rendered output addresses, so one for another network would never match
+pub fn validate_funding_address(address: &str, network: SchemaNetwork) -> Result<()> {
+    address
+        .parse::<Address<NetworkUnchecked>>()
+        .ok()
+        .and_then(|parsed| parsed.require_network(network.into()).ok())
+        .with_context(|| format!("TORTILLA: {address} is not a {network:?} address"))?;
+
+    Ok(())
+}
+
+//A referral bonus above the alkane price would make registering through your own referrer pay more
+//than it costs, so the alkane price always covers it
+pub fn validate_registration_policy(
+    policy: &SchemaRegistrationPolicy,
+    referral_bonus: u128,
+) -> Result<()> {
+    ensure!(
+        !policy.recipients.is_empty(),
+        "TORTILLA: registration needs at least one funding recipient"
+    );
+
+    let mut total_bps = 0u128;
+    for (index, recipient) in policy.recipients.iter().enumerate() {
+        ensure!(
+            !recipient.address.is_empty(),
+            "TORTILLA: funding address cannot be empty"
+        );
+        //Payments are matched per address, a repeat would let one output count twice
+        ensure!(
+            !policy.recipients[..index]
+                .iter()
+                .any(|other| other.address == recipient.address),
+            "TORTILLA: duplicate funding address {}",
+            recipient.address
+        );
+        total_bps += u128::from(recipient.share_bps);
+    }
+
+    ensure!(
+        total_bps == BPS_DENOMINATOR,
+        "TORTILLA: funding shares must add up to {BPS_DENOMINATOR} bps"
+    );
+
+    if let Some(price) = &policy.alkane_price {
+        ensure!(
+            price.base_price >= referral_bonus,
+            "TORTILLA: alkane registration price {} is below the referral bonus {referral_bonus}",
+            price.base_price
+        );
+    }
+
+    Ok(())
+}
+
+//What registering `count` more taquerias costs once `registered` exist. Rounding leftovers of the
+//split go to the first recipient so the amounts always add up to price_sats
+pub fn registration_quote(
+    policy: &SchemaRegistrationPolicy,
+    registered: u32,
+    count: u32,
+) -> Result<SchemaRegistrationQuote> {
+    let price_sats: u64 = bonding_curve_total(
+        policy.base_price_sats.into(),
+        policy.price_increment_sats.into(),
+        registered.into(),
+        count.into(),
+    )
+    .and_then(|total| u64::try_from(total).ok())
+    .context("TORTILLA: registration price overflow")?;
+
+    let mut recipients = policy
+        .recipients
+        .iter()
+        .map(|recipient| SchemaFundingRecipientQuote {
+            address: recipient.address.clone(),
+            amount_sats: (u128::from(price_sats) * u128::from(recipient.share_bps)
+                / BPS_DENOMINATOR) as u64,
+        })
+        .collect::<Vec<_>>();
+
+    let allocated: u64 = recipients.iter().map(|r| r.amount_sats).sum();
+    if let Some(first) = recipients.first_mut() {
+        first.amount_sats += price_sats - allocated;
+    }
+
+    let alkane = match &policy.alkane_price {
+        Some(price) => Some(SchemaAlkaneRegistrationQuote {
+            alkane: price.alkane,
+            amount: bonding_curve_total(
+                price.base_price,
+                price.price_increment,
+                registered.into(),
+                count.into(),
+            )
+            .context("TORTILLA: registration price overflow")?,
+        }),
+        None => None,
+    };
+
+    Ok(SchemaRegistrationQuote {
+        registered,
+        count,
+        price_sats,
+        recipients,
+        alkane,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::{SchemaAlkaneId, SchemaAlkaneRegistrationPrice, SchemaFundingRecipient};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn policy(shares: &[(&str, u16)], base_price_sats: u64) -> SchemaRegistrationPolicy {
+        SchemaRegistrationPolicy {
+            recipients: shares
+                .iter()
+                .map(|(address, share_bps)| SchemaFundingRecipient {
+                    address: address.to_string(),
+                    share_bps: *share_bps,
+                })
+                .collect(),
+            base_price_sats,
+            price_increment_sats: 7,
+            alkane_price: None,
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn bonding_curve_total_is_the_sum_of_each_price() {
+        for (base, increment, registered, count) in
+            [(21_000, 7, 0, 1), (21_000, 7, 13, 25), (5, 0, 3, 4)]
+        {
+            let summed: u128 = (0..count)
+                .map(|i| base + increment * (registered + i))
+                .sum();
+
+            assert_eq!(
+                bonding_curve_total(base, increment, registered, count),
+                Some(summed)
+            );
+        }
+
+        assert_eq!(bonding_curve_total(21_000, 7, 13, 0), Some(0));
+    }
+
+    #[wasm_bindgen_test]
+    fn bonding_curve_total_overflows_to_none() {
+        assert_eq!(bonding_curve_total(u128::MAX, 0, 0, 2), None);
+        assert_eq!(bonding_curve_total(0, u128::MAX, 1, 2), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn registration_quote_gives_the_split_remainder_to_the_first_recipient() {
+        let policy = policy(
+            &[("first", 3_333), ("second", 3_333), ("third", 3_334)],
+            10_001,
+        );
+        let quote = registration_quote(&policy, 0, 1).unwrap();
+
+        assert_eq!(quote.price_sats, 10_001);
+        assert_eq!(
+            quote
+                .recipients
+                .iter()
+                .map(|recipient| recipient.amount_sats)
+                .collect::<Vec<_>>(),
+            vec![3_334, 3_333, 3_334]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn registration_quote_prices_both_currencies_on_the_curve() {
+        let mut policy = policy(&[("first", 10_000)], 21_000);
+        policy.alkane_price = Some(SchemaAlkaneRegistrationPrice {
+            alkane: SchemaAlkaneId { block: 2, tx: 1 },
+            base_price: 1_000,
+            price_increment: 10,
+        });
+
+        let quote = registration_quote(&policy, 4, 3).unwrap();
+
+        assert_eq!(quote.price_sats, 21_000 * 3 + 7 * (4 + 5 + 6));
+        assert_eq!(quote.recipients[0].amount_sats, quote.price_sats);
+        assert_eq!(quote.alkane.unwrap().amount, 1_000 * 3 + 10 * (4 + 5 + 6));
+    }
+
+    #[wasm_bindgen_test]
+    fn registration_quote_rejects_a_price_past_u64() {
+        let policy = policy(&[("first", 10_000)], u64::MAX);
+
+        assert!(registration_quote(&policy, 0, 1).is_ok());
+        assert!(registration_quote(&policy, 0, 2).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn validate_registration_policy_checks_recipients() {
+        assert!(validate_registration_policy(
+            &policy(&[("first", 6_000), ("second", 4_000)], 1),
+            0
+        )
+        .is_ok());
+
+        //Repeated address, shares not adding up, empty address, no recipients
+        assert!(
+            validate_registration_policy(&policy(&[("first", 6_000), ("first", 4_000)], 1), 0)
+                .is_err()
+        );
+        assert!(validate_registration_policy(
+            &policy(&[("first", 6_000), ("second", 3_999)], 1),
+            0
+        )
+        .is_err());
+        assert!(validate_registration_policy(&policy(&[("", 10_000)], 1), 0).is_err());
+        assert!(validate_registration_policy(&policy(&[], 1), 0).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn alkane_price_covers_the_referral_bonus() {
+        let mut policy = policy(&[("first", 10_000)], 1);
+        policy.alkane_price = Some(SchemaAlkaneRegistrationPrice {
+            alkane: SchemaAlkaneId { block: 2, tx: 1 },
+            base_price: 1_000,
+            price_increment: 0,
+        });
+
+        assert!(validate_registration_policy(&policy, 1_000).is_ok());
+        assert!(validate_registration_policy(&policy, 1_001).is_err());
+
+        //A sats only policy has nothing to compare the bonus against
+        policy.alkane_price = None;
+        assert!(validate_registration_policy(&policy, u128::MAX).is_ok());
+    }
+}
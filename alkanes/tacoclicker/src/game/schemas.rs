@@ -1,96 +1,372 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 
 use crate::schemas::SchemaAlkaneId;
+pub use tortilla_poc::schemas::SchemaBetOnBlockParameters;
 
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
 pub struct SchemaGlobalEmissionState {
+    pub total_weight: u128,
+    pub acc_reward_per_weight: u128, //scaled by ACC_PRECISION
+    pub last_updated_block: u128,
+    pub acc_remainder: u128, //emission * ACC_PRECISION left over after dividing by total_weight
+    pub total_emitted: u128,
+    pub unallocated_emission: u128, //emitted while total_weight was 0, never minted
+}
+
+//Unscaled layout from before ACC_PRECISION, only read when migrating
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaLegacyGlobalEmissionState {
     pub total_weight: u128,
     pub acc_reward_per_weight: u128,
     pub last_updated_block: u128,
 }
 
+//Reconciliation view: total_emitted is what entered the accumulator, the rest never reached a taqueria
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaEmissionDustView {
+    pub total_emitted: u128,
+    pub unallocated_emission: u128,
+    pub acc_remainder: u128,
+    pub acc_precision: u128,
+    pub total_weight: u128,
+}
+
+//rate = initial_tortilla_per_block >> ((height - start_block) / halving_interval), halving_interval 0 keeps the rate constant
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SchemaEmissionSchedule {
+    pub start_block: u128,
+    pub initial_tortilla_per_block: u128,
+    pub halving_interval: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaGetEmissionProjectionParameters {
+    pub height: u128,
+}
+
+//Rates are for the current block, the totals are for the requested height. scheduled_emission is the
+//premine plus everything the schedule releases, which is not the minted supply: the unallocated part is
+//never minted, while salsa prizes and bet winnings are minted on top of it. Referral bonuses are minted
+//on top too, but only out of REFERRAL_POOL
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaEmissionProjection {
+    pub schedule: SchemaEmissionSchedule,
+    pub current_tortilla_per_block: u128,
+    pub next_tortilla_per_block: u128,
+    pub next_change_block: Option<u128>,
+    pub scheduled_emission: u128,
+    pub unallocated_emission: u128, //released so far while no taqueria had weight
+    pub referral_bonus_paid: u128,  //so far, never more than REFERRAL_POOL
+    pub referral_pool: u128,
+}
+
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
 pub struct SchemaTaqueriaEmissionState {
     pub taqueria_weight: u128,
+    //Whole tortilla (weight * acc / ACC_PRECISION). Legacy debts were weight * unscaled acc, which is the same number
     pub reward_debt: u128,
     pub pending: u128,
     pub last_poc_hash: Vec<u8>,
+    pub poc_count: u64, //successful proof of clicks
+}
+
+//Layout from before poc_count, only read when migrating
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaLegacyTaqueriaEmissionState {
+    pub taqueria_weight: u128,
+    pub reward_debt: u128,
+    pub pending: u128,
+    pub last_poc_hash: Vec<u8>,
+}
+
+//window_poc_count counts successful clicks since window_start_block, it drives the next retarget
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaPocDifficultyState {
+    pub difficulty_bits: u8,
+    pub window_start_block: u128,
+    pub window_poc_count: u64,
+    pub total_poc_count: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaPocDifficultyView {
+    pub state: SchemaPocDifficultyState,
+    pub next_retarget_block: u128,
+    pub target_per_interval: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SchemaUpgradeCatalogueEntry {
+    pub id: u8,
+    pub name: String,
+    pub base_cost: u128,
+    pub weight: u128,
+    //next_price = price * numerator / denominator after every buy
+    pub price_growth_numerator: u128,
+    pub price_growth_denominator: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaUpgradeCatalogue {
+    pub upgrades: Vec<SchemaUpgradeCatalogueEntry>,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
 pub struct SchemaUpgradesEntry {
+    pub id: u8,
+    pub name: String,
     pub cost: u128,
     pub weight: u128,
     pub current_emission: u128,
 }
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
 pub struct SchemaUpgradesView {
-    pub taquero: SchemaUpgradesEntry,
-    pub salsa_bar: SchemaUpgradesEntry,
-    pub tortilla_tree: SchemaUpgradesEntry,
-    pub tortilla_factory: SchemaUpgradesEntry,
-    pub taco_submarine: SchemaUpgradesEntry,
-    pub taco_pyramid: SchemaUpgradesEntry,
-    pub tortilla_spaceship: SchemaUpgradesEntry,
-    pub satoshi_tacomoto: SchemaUpgradesEntry,
+    pub upgrades: Vec<SchemaUpgradesEntry>,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
 pub struct SchemaUserUpgradesEntry {
+    pub id: u8,
     pub amount: u128,
     pub next_price: u128, //Increases by 1.5x on each buy. 50000, 75000, etc etc. This is to incentivize people to chase bigger upgrades
 }
 
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
 pub struct SchemaGlobalSalsaState {
+    pub current_block: u128, //first block of the round being played
+    pub best_hash: Vec<u8>,
+    pub best_hash_owner: Vec<u8>,
+    pub round: u128,
+    pub prize: u128, //salsa_block_reward snapshotted when the round opened, paid out by ClaimSalsaPrize
+}
+
+//Layout from before salsa rounds were escrowed, only read when migrating
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaLegacyGlobalSalsaState {
     pub current_block: u128,
     pub best_hash: Vec<u8>,
-    pub best_hash_owner: Vec<u8>, //Increases by 1.5x on each buy. 50000, 75000, etc etc. This is to incentivize people to chase bigger upgrades
+    pub best_hash_owner: Vec<u8>,
+}
+
+//History entry written when a round with a winner closes
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaSalsaRound {
+    pub round: u128,
+    pub round_block: u128,
+    pub winning_hash: Vec<u8>,
+    pub winner: SchemaAlkaneId,
+    pub prize: u128,
+    pub claimed: bool,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaSalsaRoundParameters {
+    pub round: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaSalsaRoundView {
+    pub round: u128,
+    pub round_block: u128,
+    pub best_hash: Vec<u8>,
+    pub leader: Option<SchemaAlkaneId>, //winner once finished
+    pub prize: u128,
+    pub finished: bool,
+    pub claimed: bool,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
 pub struct SchemaUserUpgradesView {
-    pub taquero: SchemaUserUpgradesEntry,
-    pub salsa_bar: SchemaUserUpgradesEntry,
-    pub tortilla_tree: SchemaUserUpgradesEntry,
-    pub tortilla_factory: SchemaUserUpgradesEntry,
-    pub taco_submarine: SchemaUserUpgradesEntry,
-    pub taco_pyramid: SchemaUserUpgradesEntry,
-    pub tortilla_spaceship: SchemaUserUpgradesEntry,
-    pub satoshi_tacomoto: SchemaUserUpgradesEntry,
-}
-
-#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
-pub enum UpgradeKind {
-    Taquero,
-    SalsaBar,
-    TortillaTree,
-    TortillaFactory,
-    TacoBank,
-    TacoPyramid,
-    TortillaSpaceship,
-    SatoshiTacomoto,
-}
-
-impl From<UpgradeKind> for u8 {
-    fn from(kind: UpgradeKind) -> Self {
-        match kind {
-            UpgradeKind::Taquero => 0,
-            UpgradeKind::SalsaBar => 1,
-            UpgradeKind::TortillaTree => 2,
-            UpgradeKind::TortillaFactory => 3,
-            UpgradeKind::TacoBank => 4,
-            UpgradeKind::TacoPyramid => 5,
-            UpgradeKind::TortillaSpaceship => 6,
-            UpgradeKind::SatoshiTacomoto => 7,
-        }
+    pub upgrades: Vec<SchemaUserUpgradesEntry>,
+}
+
+//Layout from before the catalogue, one blob per taqueria holding the eight seed tiers. Only read when migrating
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaLegacyUserUpgradesEntry {
+    pub amount: u128,
+    pub next_price: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaLegacyUserUpgradesView {
+    pub taquero: SchemaLegacyUserUpgradesEntry,
+    pub salsa_bar: SchemaLegacyUserUpgradesEntry,
+    pub tortilla_tree: SchemaLegacyUserUpgradesEntry,
+    pub tortilla_factory: SchemaLegacyUserUpgradesEntry,
+    pub taco_submarine: SchemaLegacyUserUpgradesEntry,
+    pub taco_pyramid: SchemaLegacyUserUpgradesEntry,
+    pub tortilla_spaceship: SchemaLegacyUserUpgradesEntry,
+    pub satoshi_tacomoto: SchemaLegacyUserUpgradesEntry,
+}
+
+impl SchemaLegacyUserUpgradesView {
+    //Field order is the seed catalogue id order
+    pub fn into_entries(self) -> Vec<SchemaUserUpgradesEntry> {
+        [
+            self.taquero,
+            self.salsa_bar,
+            self.tortilla_tree,
+            self.tortilla_factory,
+            self.taco_submarine,
+            self.taco_pyramid,
+            self.tortilla_spaceship,
+            self.satoshi_tacomoto,
+        ]
+        .into_iter()
+        .zip(0u8..)
+        .map(|(legacy, id)| SchemaUserUpgradesEntry {
+            id,
+            amount: legacy.amount,
+            next_price: legacy.next_price,
+        })
+        .collect()
     }
 }
 
 //buy_upgrade
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
 pub struct SchemaBuyUpgradeParameters {
-    pub upgrade: UpgradeKind,
+    pub upgrade: u8,   //id in the upgrade catalogue
+    pub quantity: u32, //max units to buy, fewer are bought if the tortilla sent runs out
+    pub taqueria: Option<SchemaAlkaneId>,
+}
+
+//Actions take an optional taqueria, it must be one of the registered taquerias sent in. None picks the first
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaTaqueriaSelectionParameters {
+    pub taqueria: Option<SchemaAlkaneId>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaBuyUpgradeResponse {
+    pub bought: u128,
+    pub total_spent: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaTaqueriaCountResponse {
+    pub count: u32,
+}
+
+//Taquerias registered before the list existed, in the order they were registered
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaBackfillTaqueriaListParameters {
+    pub taquerias: Vec<SchemaAlkaneId>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaListTaqueriasParameters {
+    pub offset: u32,
+    pub limit: u32,
+    pub include_state: bool, //also load weight and live unclaimed tortilla for each entry
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaTaqueriaListState {
+    pub taqueria_weight: u128,
+    pub unclaimed_tortilla: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaTaqueriaListEntry {
+    pub taqueria: SchemaAlkaneId,
+    pub state: Option<SchemaTaqueriaListState>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaTaqueriaListView {
+    pub total: u32,
+    pub taquerias: Vec<SchemaTaqueriaListEntry>,
+}
+
+//Stored sorted by weight, highest first
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Default)]
+pub struct SchemaLeaderboard {
+    pub entries: Vec<SchemaLeaderboardStoredEntry>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaLeaderboardStoredEntry {
+    pub taqueria: SchemaAlkaneId,
+    pub weight: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaLeaderboardEntry {
+    pub rank: u32, //1 based
+    pub taqueria: SchemaAlkaneId,
+    pub weight: u128,
+    pub share: u128,                //of total_weight, scaled by SHARE_SCALE
+    pub upgrade_amounts: Vec<u128>, //indexed by upgrade id
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaLeaderboardView {
+    pub total_weight: u128,
+    pub entries: Vec<SchemaLeaderboardEntry>,
+}
+
+//sell_upgrade
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaSellUpgradeParameters {
+    pub upgrade: u8,
+    pub taqueria: Option<SchemaAlkaneId>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaSellUpgradeResponse {
+    pub refund_amount: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub enum SchemaCompoundStrategy {
+    Upgrade { upgrade: u8 },
+    //Greedy on current_emission / cost as listed by GetAvailableUpgrades, re-ranked after every unit
+    BestEmissionPerCost,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaCompoundParameters {
+    pub strategy: SchemaCompoundStrategy,
+    pub taqueria: Option<SchemaAlkaneId>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaCompoundPurchase {
+    pub upgrade: u8,
+    pub bought: u128,
+    pub spent: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaCompoundResponse {
+    pub purchases: Vec<SchemaCompoundPurchase>,
+    pub total_spent: u128,
+    pub remaining_pending: u128,
+}
+
+//ClaimTortillaAirdrop can be called without inputs, which only claims and sends the airdrop to output 0.
+//register and invest need the airdrop owner's consent: an input of this tx signed by the output 0 key
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaClaimTortillaAirdropParameters {
+    pub register: Option<SchemaRegisterParameters>, //registers a new taqueria, paid the same as Register
+    pub invest: Option<SchemaCompoundStrategy>, //spends the airdrop on upgrades like Compound, the rest is sent out
+    pub taqueria: Option<SchemaAlkaneId>, //taqueria to invest into when not registering, None is the first sent in
+    pub prevouts: Vec<SchemaPrevout>, //outputs this tx spends in input order, only read for register or invest
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaPrevout {
+    pub value: u64, //sats
+    pub script_pubkey: Vec<u8>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaClaimTortillaAirdropResponse {
+    pub claimed: u128,
+    pub taqueria: Option<SchemaAlkaneId>,
+    pub purchases: Vec<SchemaCompoundPurchase>,
+    pub total_spent: u128,
 }
 
 //get available upgrades
@@ -112,11 +388,96 @@ pub struct SchemaGetMultiplierFromHashResponse {
     pub multiplier: u128,
 }
 
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaPreviewBetParameters {
+    pub target_multiplier: u128,
+    pub amount: u128,
+}
+
+//Probabilities and multipliers are scaled by 1e8 like multiplier_from_seed. winning_seeds counts the
+//64 bit hash prefixes (out of 2^64) that beat the target, so it is the exact probability
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaPreviewBetResponse {
+    pub winning_seeds: u128,
+    pub win_probability: u128,
+    pub expected_multiplier: u128, //mean of the multiplier over winning seeds, 0 for losing ones
+    pub expected_payout: u128,
+    pub max_payout: u128,
+}
+
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
 
 pub struct SchemaGetUnclaimedTortillaResponse {
     pub unclaimed_tortilla: u128,
 }
+
+//Register can be called without inputs, which is the same as all None. name and symbol go on the TAQUERIA token
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaRegisterParameters {
+    pub referrer: Option<SchemaAlkaneId>,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaRenameTaqueriaParameters {
+    pub name: String,
+    pub taqueria: Option<SchemaAlkaneId>,
+}
+
+//The token name cant change after the clone, so renames are only a Tortilla side override
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaTaqueriaDisplayName {
+    pub name: String,
+    pub renamed: bool,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaRegisterManyParameters {
+    pub count: u32,
+}
+
+//GetRegistrationQuote without inputs quotes a single taqueria
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaGetRegistrationQuoteParameters {
+    pub count: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaReferral {
+    pub referrer: SchemaAlkaneId,
+    pub block: u128,
+    pub bonus: u128, //credited to the referrer's pending at registration
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaGetReferralsParameters {
+    pub taqueria: SchemaAlkaneId,
+    pub offset: u32,
+    pub limit: u32,
+}
+
+//referred_by is who brought this taqueria in, referred the taquerias it brought in (paginated, oldest first)
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaReferralsView {
+    pub referred_by: Option<SchemaReferral>,
+    pub total_referred: u32,
+    pub total_bonus: u128,
+    pub referred: Vec<SchemaAlkaneId>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaClaimAllEntry {
+    pub taqueria: SchemaAlkaneId,
+    pub claimed: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaClaimAllResponse {
+    pub total_claimed: u128,
+    pub claims: Vec<SchemaClaimAllEntry>,
+}
+
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
 
 pub struct SchemaGetTortillaPerBlockResponse {
@@ -125,17 +486,98 @@ pub struct SchemaGetTortillaPerBlockResponse {
 
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
 
-pub struct SchemaBetOnBlockParameters {
+pub struct SchemaBetOnBlockResponse {
+    pub won_amount: u128,
+    pub lost_amount: u128,
+    pub stake_amount: u128,
+    pub payout: u128,            //stake * multiplier on a win, 0 on a loss
+    pub remaining_pending: u128, //unclaimed tortilla after the bet, payout included
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaCommitBetParameters {
     pub nonce_found_poc: u128,
     pub target_multiplier: u128,
+    pub taqueria: Option<SchemaAlkaneId>,
 }
 
+//One open commit per taqueria. locked_amount left pending when the bet was committed
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaBetCommit {
+    pub locked_amount: u128,
+    pub target_multiplier: u128,
+    pub committed_block: u128,
+    pub target_block: u128,
+}
 
-pub struct SchemaBetOnBlockResponse {
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaBetCommitView {
+    pub commit: Option<SchemaBetCommit>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaRevealBetResponse {
     pub won_amount: u128,
     pub lost_amount: u128,
+    pub multiplier: u128, //0 when the commit expired or its target hash was never recorded
+    pub expired: bool,
+}
+
+//forfeited is the settlement of an expired commit the new one replaced
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaCommitBetResponse {
+    pub commit: SchemaBetCommit,
+    pub forfeited: Option<SchemaRevealBetResponse>,
+}
+
+//block is the one whose hash decided the bet (the target block for a revealed commit)
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaBetHistoryEntry {
+    pub block: u128,
+    pub target_multiplier: u128,
+    pub multiplier: u128,
+    pub stake_amount: u128,
+    pub won_amount: u128,
+    pub lost_amount: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Default)]
+pub struct SchemaTaqueriaBetStats {
+    pub bets: u64,
+    pub wins: u64,
+    pub total_staked: u128,
+    pub total_won: u128,
+    pub total_lost: u128,
 }
+
+//TORTILLA created by winning bets and burned by losing ones, across every taqueria
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Default)]
+pub struct SchemaGlobalBetStats {
+    pub bets: u64,
+    pub total_created: u128,
+    pub total_burned: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaGetBetHistoryParameters {
+    pub taqueria: SchemaAlkaneId,
+    pub offset: u32,
+    pub limit: u32,
+}
+
+//Newest first. total is how many entries are still retained, not the lifetime bet count
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaBetHistoryView {
+    pub total: u32,
+    pub entries: Vec<SchemaBetHistoryEntry>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaBetStatsView {
+    pub taqueria: SchemaTaqueriaBetStats,
+    pub global: SchemaGlobalBetStats,
+}
+
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
 pub struct SchemaCompleteGlobalState {
     pub emission_state: SchemaGlobalEmissionState,
@@ -1,21 +1,33 @@
 //Store all pointers for taco clicker here.
 
-use crate::consts::{SALSA_BLOCK_REWARD, TORTILLA_PER_BLOCK};
-use crate::game::consts::UPGRADES;
+use crate::consts::{legacy_params, REFERRAL_POOL};
+use crate::game::consts::{
+    BET_HISTORY_SIZE, GLOBAL_EMISSION_STATE_VERSION, SALSA_ROUND_BLOCKS, STARTER_UPGRADE_ID,
+};
+use crate::game::emissions::{
+    accrue_global_emission, accrued_reward, migrate_legacy_global_emission_state,
+};
+use crate::game::poc::{default_poc_difficulty, proof_of_click_hash, retarget_poc_difficulty};
 use crate::game::schemas::{
-    SchemaGlobalEmissionState, SchemaGlobalSalsaState, SchemaTaqueriaEmissionState,
+    SchemaBetCommit, SchemaBetHistoryEntry, SchemaGlobalBetStats, SchemaGlobalEmissionState,
+    SchemaGlobalSalsaState, SchemaLeaderboard, SchemaLegacyGlobalEmissionState,
+    SchemaLegacyGlobalSalsaState, SchemaLegacyTaqueriaEmissionState, SchemaLegacyUserUpgradesView,
+    SchemaPocDifficultyState, SchemaReferral, SchemaSalsaRound, SchemaTaqueriaBetStats,
+    SchemaTaqueriaEmissionState, SchemaUpgradeCatalogue, SchemaUpgradeCatalogueEntry,
     SchemaUserUpgradesEntry,
 };
+use crate::game::utils::{default_upgrade_catalogue, get_upgrade_by_id, rank_on_leaderboard};
+use crate::schemas::{SchemaAdminChange, SchemaAdminLogEntry, SchemaTacoClickerParams};
 use crate::utils::encoders::decode_from_vec;
 use crate::Tortilla;
 use crate::{game::schemas::SchemaUserUpgradesView, schemas::SchemaAlkaneId};
 use alkanes_runtime::runtime::AlkaneResponder;
 use alkanes_runtime::storage::StoragePointer;
-use anyhow::{ensure, Context, Result};
+use anyhow::{anyhow, ensure, Context, Result};
 use bitcoin::hashes::Hash;
 use borsh::BorshDeserialize;
 use metashrew_support::index_pointer::KeyValuePointer;
-use sha2::{Digest, Sha256};
+use sha2::Digest;
 use std::sync::Arc;
 
 impl Tortilla {
@@ -23,15 +35,128 @@ impl Tortilla {
         StoragePointer::from_keyword("/consts")
     }
 
+    pub fn get_admin_pointer(&self) -> StoragePointer {
+        StoragePointer::from_keyword("/admin")
+    }
+
+    pub fn get_params_pointer(&self) -> StoragePointer {
+        StoragePointer::from_keyword("/params")
+    }
+
+    //Append-only list (length + index), one SchemaAdminLogEntry per admin action
+    pub fn get_admin_log_pointer(&self) -> StoragePointer {
+        StoragePointer::from_keyword("/admin_log")
+    }
+
     pub fn get_global_salsa_state_pointer(&self) -> StoragePointer {
         StoragePointer::from_keyword("/salsa_global_state")
     }
 
+    //Hashes of blocks the contract ran in (or right after), so bets can settle on a past block
+    pub fn get_block_hash_pointer(&self, height: u128) -> StoragePointer {
+        StoragePointer::from_keyword("/block_hashes").select(&height.to_le_bytes().to_vec())
+    }
+
+    pub fn get_bet_commit_pointer(&self, taqueria: &SchemaAlkaneId) -> Result<StoragePointer> {
+        Ok(StoragePointer::from_keyword("/bet_commits").select(
+            &borsh::to_vec(taqueria).context("TORTILLA: failed to get bet commit pointer")?,
+        ))
+    }
+
+    //Ring buffer of the last BET_HISTORY_SIZE bets, slot = bet number % BET_HISTORY_SIZE
+    pub fn get_bet_history_pointer(&self, taqueria: &SchemaAlkaneId) -> Result<StoragePointer> {
+        Ok(StoragePointer::from_keyword("/bet_history").select(
+            &borsh::to_vec(taqueria).context("TORTILLA: failed to get bet history pointer")?,
+        ))
+    }
+
+    pub fn get_bet_stats_pointer(&self, taqueria: &SchemaAlkaneId) -> Result<StoragePointer> {
+        Ok(StoragePointer::from_keyword("/bet_stats")
+            .select(&borsh::to_vec(taqueria).context("TORTILLA: failed to get bet stats pointer")?))
+    }
+
+    pub fn get_global_bet_stats_pointer(&self) -> StoragePointer {
+        StoragePointer::from_keyword("/global_bet_stats")
+    }
+
+    //Closed salsa rounds keyed by round number
+    pub fn get_salsa_round_pointer(&self, round: u128) -> StoragePointer {
+        StoragePointer::from_keyword("/salsa_rounds").select(&round.to_le_bytes().to_vec())
+    }
+
     pub fn get_taquerias_pointer(&self, taqueria: &SchemaAlkaneId) -> Result<StoragePointer> {
         Ok(StoragePointer::from_keyword("/taquerias")
             .select(&borsh::to_vec(taqueria).context("TORTILLA: failed to get taquerias pointer")?))
     }
 
+    //Append-only list of taqueria ids in registration order, filled from register on. /taquerias stays the membership check.
+    //Its length is the registered count the registration price is quoted at, so older taquerias are back-filled by the admin
+    pub fn get_taqueria_list_pointer(&self) -> StoragePointer {
+        StoragePointer::from_keyword("/taqueria_list")
+    }
+
+    //Who referred a taqueria, set once at registration
+    pub fn get_referral_pointer(&self, taqueria: &SchemaAlkaneId) -> Result<StoragePointer> {
+        Ok(StoragePointer::from_keyword("/referrals")
+            .select(&borsh::to_vec(taqueria).context("TORTILLA: failed to get referral pointer")?))
+    }
+
+    //Append list of the taquerias a referrer brought in
+    pub fn get_referred_list_pointer(&self, referrer: &SchemaAlkaneId) -> Result<StoragePointer> {
+        Ok(StoragePointer::from_keyword("/referred").select(
+            &borsh::to_vec(referrer).context("TORTILLA: failed to get referred list pointer")?,
+        ))
+    }
+
+    pub fn get_referral_bonus_total_pointer(
+        &self,
+        referrer: &SchemaAlkaneId,
+    ) -> Result<StoragePointer> {
+        Ok(
+            StoragePointer::from_keyword("/referral_bonus_total").select(
+                &borsh::to_vec(referrer)
+                    .context("TORTILLA: failed to get referral bonus pointer")?,
+            ),
+        )
+    }
+
+    //Display name override set by RenameTaqueria
+    pub fn get_taqueria_display_name_pointer(
+        &self,
+        taqueria: &SchemaAlkaneId,
+    ) -> Result<StoragePointer> {
+        Ok(
+            StoragePointer::from_keyword("/taqueria_display_names").select(
+                &borsh::to_vec(taqueria).context("TORTILLA: failed to get display name pointer")?,
+            ),
+        )
+    }
+
+    //Name the taqueria token was created with, only stored when it isnt the default
+    pub fn get_taqueria_token_name_pointer(
+        &self,
+        taqueria: &SchemaAlkaneId,
+    ) -> Result<StoragePointer> {
+        Ok(
+            StoragePointer::from_keyword("/taqueria_token_names").select(
+                &borsh::to_vec(taqueria).context("TORTILLA: failed to get token name pointer")?,
+            ),
+        )
+    }
+
+    //Referral bonuses paid to everyone so far, out of REFERRAL_POOL
+    pub fn get_referral_bonus_paid_pointer(&self) -> StoragePointer {
+        StoragePointer::from_keyword("/referral_bonus_paid")
+    }
+
+    pub fn get_leaderboard_pointer(&self) -> StoragePointer {
+        StoragePointer::from_keyword("/leaderboard")
+    }
+
+    pub fn get_upgrade_catalogue_pointer(&self) -> StoragePointer {
+        StoragePointer::from_keyword("/upgrade_catalogue")
+    }
+
     pub fn get_taqueria_upgrades_pointer(
         &self,
         taqueria: &SchemaAlkaneId,
@@ -42,6 +167,17 @@ impl Tortilla {
         ))
     }
 
+    //Ownership is a map keyed by upgrade id under the taqueria, so new catalogue tiers need no storage migration
+    pub fn get_taqueria_upgrade_pointer(
+        &self,
+        taqueria: &SchemaAlkaneId,
+        upgrade_id: u8,
+    ) -> Result<StoragePointer> {
+        Ok(self
+            .get_taqueria_upgrades_pointer(taqueria)?
+            .select(&vec![upgrade_id]))
+    }
+
     pub fn get_taqueria_emission_state_pointer(
         &self,
         taqueria: &SchemaAlkaneId,
@@ -57,163 +193,740 @@ impl Tortilla {
     pub fn get_global_emission_state_pointer(&self) -> StoragePointer {
         StoragePointer::from_keyword("/global_emission")
     }
+
+    pub fn get_poc_difficulty_pointer(&self) -> StoragePointer {
+        StoragePointer::from_keyword("/poc_difficulty")
+    }
+
+    //Layout version of /global_emission, missing (0) on deployments from before ACC_PRECISION
+    pub fn get_global_emission_version_pointer(&self) -> StoragePointer {
+        StoragePointer::from_keyword("/global_emission_version")
+    }
 }
 
 //Storage mutation helper libs
 impl Tortilla {
-    pub fn update_global(&self) -> Result<()> {
-        let now_block: u128 = self.height().into();
+    //None on deployments from before the admin role, until BootstrapAdmin runs
+    pub fn get_admin_value(&self) -> Result<Option<SchemaAlkaneId>> {
+        let bytes = (*self.get_admin_pointer().get()).clone();
 
-        let mut ptr = self.get_global_emission_state_pointer();
-        let bytes = (*ptr.get()).clone();
+        if bytes.is_empty() {
+            return Ok(None);
+        }
 
-        let mut state: SchemaGlobalEmissionState =
-            decode_from_vec!(bytes, SchemaGlobalEmissionState)
-                .context("TORTILLA: failed to decode global emission state")?;
+        Ok(Some(decode_from_vec!(bytes, SchemaAlkaneId)?))
+    }
 
-        // 2. No‑op guard – already updated for this block ──────────────────────────
-        if now_block == state.last_updated_block {
-            return Ok(());
+    pub fn set_admin(&self, admin: &SchemaAlkaneId) -> Result<()> {
+        self.get_admin_pointer().set(Arc::new(
+            borsh::to_vec(admin).context("TORTILLA: failed to encode admin")?,
+        ));
+        Ok(())
+    }
+
+    pub fn get_params_value(&self) -> Result<SchemaTacoClickerParams> {
+        let bytes = (*self.get_params_pointer().get()).clone();
+
+        //Deployments from before params lived in storage keep the compile time defaults at a flat rate
+        if bytes.is_empty() {
+            return Ok(legacy_params());
         }
 
-        // 3. Gap length in blocks (checked math prevents underflow) ───────────────
-        let blocks = now_block
-            .checked_sub(state.last_updated_block)
-            .context("TORTILLA: block underflow")?;
+        decode_from_vec!(bytes, SchemaTacoClickerParams)
+    }
+
+    pub fn set_params(&self, params: &SchemaTacoClickerParams) -> Result<()> {
+        self.get_params_pointer().set(Arc::new(
+            borsh::to_vec(params).context("TORTILLA: failed to encode params")?,
+        ));
+        Ok(())
+    }
 
-        // 4. Mint only if someone is staked ───────────────────────────────────────
-        if state.total_weight > 0 {
-            // ΔACC = blocks * EMISSION_PER_BLOCK * SCALE / total_weight
-            let delta = (blocks as u128).saturating_mul(TORTILLA_PER_BLOCK) / state.total_weight;
+    pub fn append_admin_log(&self, change: SchemaAdminChange) -> Result<()> {
+        let entry = SchemaAdminLogEntry {
+            block: self.height().into(),
+            change,
+        };
 
-            state.acc_reward_per_weight = state.acc_reward_per_weight.saturating_add(delta);
-        }
+        self.get_admin_log_pointer().append(Arc::new(
+            borsh::to_vec(&entry).context("TORTILLA: failed to encode admin log entry")?,
+        ));
+        Ok(())
+    }
 
-        // 5. Slide the cursor forward ─────────────────────────────────────────────
-        state.last_updated_block = now_block;
+    pub fn get_upgrade_catalogue_value(&self) -> Result<SchemaUpgradeCatalogue> {
+        let bytes = (*self.get_upgrade_catalogue_pointer().get()).clone();
+
+        //Deployments from before the catalogue lived in storage fall back to the seed table
+        if bytes.is_empty() {
+            return Ok(default_upgrade_catalogue());
+        }
 
-        // 6. Persist the mutated struct back to storage ───────────────────────────
-        let encoded =
-            borsh::to_vec(&state).context("TORTILLA: failed to encode global emission state")?;
-        ptr.set(Arc::new(encoded));
+        decode_from_vec!(bytes, SchemaUpgradeCatalogue)
+    }
 
+    pub fn set_upgrade_catalogue(&self, catalogue: &SchemaUpgradeCatalogue) -> Result<()> {
+        self.get_upgrade_catalogue_pointer().set(Arc::new(
+            borsh::to_vec(catalogue).context("TORTILLA: failed to encode upgrade catalogue")?,
+        ));
         Ok(())
     }
 
-    pub fn create_taqueria_deps(&self, taqueria: &SchemaAlkaneId) -> Result<()> {
-        let mut upgrades_slot_pointer = self.get_taqueria_upgrades_pointer(taqueria)?;
+    //Appends a tier to the catalogue. Ids follow the catalogue order, so existing ids never move
+    pub fn add_upgrade_to_catalogue(
+        &self,
+        name: String,
+        base_cost: u128,
+        weight: u128,
+        price_growth_numerator: u128,
+        price_growth_denominator: u128,
+    ) -> Result<SchemaUpgradeCatalogueEntry> {
+        ensure!(
+            base_cost > 0 && weight > 0,
+            "TORTILLA: upgrade base cost and weight must be non zero"
+        );
+        ensure!(
+            price_growth_denominator > 0 && price_growth_numerator >= price_growth_denominator,
+            "TORTILLA: upgrade price growth must be at least 1x"
+        );
 
-        let mut taqueria_emission_state_pointer =
-            self.get_taqueria_emission_state_pointer(taqueria)?;
+        let mut catalogue = self.get_upgrade_catalogue_value()?;
+        let id: u8 = catalogue
+            .upgrades
+            .len()
+            .try_into()
+            .map_err(|_| anyhow!("TORTILLA: upgrade catalogue is full"))?;
+
+        let upgrade = SchemaUpgradeCatalogueEntry {
+            id,
+            name,
+            base_cost,
+            weight,
+            price_growth_numerator,
+            price_growth_denominator,
+        };
 
-        let mut global_emission_state_pointer = self.get_global_emission_state_pointer();
+        catalogue.upgrades.push(upgrade.clone());
+        self.set_upgrade_catalogue(&catalogue)?;
 
-        let default_user_upgrades_view = SchemaUserUpgradesView {
-            taquero: SchemaUserUpgradesEntry {
-                amount: 1u128,
-                next_price: UPGRADES.taquero.base_cost,
-            },
-            salsa_bar: SchemaUserUpgradesEntry {
-                amount: 0u128,
-                next_price: UPGRADES.salsa_bar.base_cost,
-            },
-            tortilla_tree: SchemaUserUpgradesEntry {
-                amount: 0u128,
-                next_price: UPGRADES.tortilla_tree.base_cost,
-            },
-            tortilla_factory: SchemaUserUpgradesEntry {
-                amount: 0u128,
-                next_price: UPGRADES.tortilla_factory.base_cost,
-            },
-            taco_submarine: SchemaUserUpgradesEntry {
-                amount: 0u128,
-                next_price: UPGRADES.taco_submarine.base_cost,
-            },
-            taco_pyramid: SchemaUserUpgradesEntry {
-                amount: 0u128,
-                next_price: UPGRADES.taco_pyramid.base_cost,
-            },
-            tortilla_spaceship: SchemaUserUpgradesEntry {
-                amount: 0u128,
-                next_price: UPGRADES.tortilla_spaceship.base_cost,
-            },
-            satoshi_tacomoto: SchemaUserUpgradesEntry {
+        Ok(upgrade)
+    }
+
+    pub fn get_taqueria_upgrade(
+        &self,
+        taqueria: &SchemaAlkaneId,
+        upgrade: &SchemaUpgradeCatalogueEntry,
+    ) -> Result<SchemaUserUpgradesEntry> {
+        let bytes = (*self
+            .get_taqueria_upgrade_pointer(taqueria, upgrade.id)?
+            .get())
+        .clone();
+
+        //Never bought yet, nothing stored
+        if bytes.is_empty() {
+            //Taquerias from before the catalogue still hold the seed tiers in the legacy blob
+            if let Some(legacy) = self
+                .get_legacy_taqueria_upgrades_value(taqueria)?
+                .and_then(|entries| entries.into_iter().find(|entry| entry.id == upgrade.id))
+            {
+                return Ok(legacy);
+            }
+
+            return Ok(SchemaUserUpgradesEntry {
+                id: upgrade.id,
                 amount: 0u128,
-                next_price: UPGRADES.satoshi_tacomoto.base_cost,
-            },
-        };
+                next_price: upgrade.base_cost,
+            });
+        }
+
+        decode_from_vec!(bytes, SchemaUserUpgradesEntry)
+    }
+
+    pub fn set_taqueria_upgrade(
+        &self,
+        taqueria: &SchemaAlkaneId,
+        entry: &SchemaUserUpgradesEntry,
+    ) -> Result<()> {
+        //First write to a legacy taqueria moves every seed tier to its own pointer and drops the blob,
+        //so later reads never mix the two layouts
+        if let Some(legacy) = self.get_legacy_taqueria_upgrades_value(taqueria)? {
+            for legacy_entry in legacy {
+                self.get_taqueria_upgrade_pointer(taqueria, legacy_entry.id)?
+                    .set(Arc::new(borsh::to_vec(&legacy_entry).context(
+                        "TORTILLA: failed to encode taqueria upgrade entry",
+                    )?));
+            }
+            self.get_taqueria_upgrades_pointer(taqueria)?
+                .set(Arc::new(Vec::new()));
+        }
+
+        self.get_taqueria_upgrade_pointer(taqueria, entry.id)?
+            .set(Arc::new(borsh::to_vec(entry).context(
+                "TORTILLA: failed to encode taqueria upgrade entry",
+            )?));
+        Ok(())
+    }
+
+    pub fn get_legacy_taqueria_upgrades_value(
+        &self,
+        taqueria: &SchemaAlkaneId,
+    ) -> Result<Option<Vec<SchemaUserUpgradesEntry>>> {
+        let bytes = (*self.get_taqueria_upgrades_pointer(taqueria)?.get()).clone();
+
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            decode_from_vec!(bytes, SchemaLegacyUserUpgradesView)?.into_entries(),
+        ))
+    }
+
+    pub fn get_taqueria_upgrades(
+        &self,
+        taqueria: &SchemaAlkaneId,
+        catalogue: &SchemaUpgradeCatalogue,
+    ) -> Result<SchemaUserUpgradesView> {
+        Ok(SchemaUserUpgradesView {
+            upgrades: catalogue
+                .upgrades
+                .iter()
+                .map(|upgrade| self.get_taqueria_upgrade(taqueria, upgrade))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    pub fn get_leaderboard_value(&self) -> Result<SchemaLeaderboard> {
+        let bytes = (*self.get_leaderboard_pointer().get()).clone();
 
-        upgrades_slot_pointer.set(Arc::new(
-            borsh::to_vec(&default_user_upgrades_view)
-                .context("TORTILLA: Failed to set upgrades slot pointer")?,
+        if bytes.is_empty() {
+            return Ok(SchemaLeaderboard::default());
+        }
+
+        decode_from_vec!(bytes, SchemaLeaderboard)
+    }
+
+    pub fn update_leaderboard(&self, taqueria: &SchemaAlkaneId, weight: u128) -> Result<()> {
+        let mut leaderboard = self.get_leaderboard_value()?;
+        rank_on_leaderboard(&mut leaderboard, taqueria, weight);
+
+        self.get_leaderboard_pointer().set(Arc::new(
+            borsh::to_vec(&leaderboard).context("TORTILLA: failed to encode leaderboard")?,
         ));
+        Ok(())
+    }
 
-        let default_user_emission_state_view = SchemaTaqueriaEmissionState {
-            taqueria_weight: UPGRADES.taquero.weight,
-            reward_debt: 0u128,
-            pending: 0u128,
-            last_poc_hash: Vec::new(),
-        };
+    pub fn get_global_emission_state_value(&self) -> Result<SchemaGlobalEmissionState> {
+        let bytes = (*self.get_global_emission_state_pointer().get()).clone();
 
-        taqueria_emission_state_pointer.set(Arc::new(
-            borsh::to_vec(&default_user_emission_state_view)
-                .context("TORTILLA: failed to encode default taqueria emission state")?,
+        //Deployments from before ACC_PRECISION hold the unscaled layout. It is scaled here on read and
+        //persisted in the new layout by the next set_global_emission_state
+        if self.get_global_emission_version_pointer().get_value::<u8>()
+            < GLOBAL_EMISSION_STATE_VERSION
+        {
+            let legacy = decode_from_vec!(bytes, SchemaLegacyGlobalEmissionState)?;
+            return migrate_legacy_global_emission_state(&legacy);
+        }
+
+        decode_from_vec!(bytes, SchemaGlobalEmissionState)
+    }
+
+    pub fn set_global_emission_state(&self, state: &SchemaGlobalEmissionState) -> Result<()> {
+        self.get_global_emission_state_pointer().set(Arc::new(
+            borsh::to_vec(state).context("TORTILLA: failed to encode global emission state")?,
         ));
+        self.get_global_emission_version_pointer()
+            .set_value::<u8>(GLOBAL_EMISSION_STATE_VERSION);
+        Ok(())
+    }
 
-        let global_emission_bytes = (*global_emission_state_pointer.get()).clone();
+    pub fn get_taqueria_emission_state_value(
+        &self,
+        taqueria: &SchemaAlkaneId,
+    ) -> Result<SchemaTaqueriaEmissionState> {
+        let bytes = (*self
+            .get_taqueria_emission_state_pointer(taqueria)
+            .context("TORTILLA: taqueria emission state not found")?
+            .get())
+        .clone();
 
-        let mut global_emission_state =
-            decode_from_vec!(global_emission_bytes, SchemaGlobalEmissionState)?;
+        if let Ok(state) = SchemaTaqueriaEmissionState::try_from_slice(&bytes) {
+            return Ok(state);
+        }
 
-        global_emission_state.total_weight += 1;
+        //Taquerias registered before poc_count start counting from zero
+        let legacy = decode_from_vec!(bytes, SchemaLegacyTaqueriaEmissionState)?;
 
-        global_emission_state_pointer
-            .set(Arc::new(borsh::to_vec(&global_emission_state).context(
-                "TORTILLA: Failed to encode global emission state",
-            )?));
+        Ok(SchemaTaqueriaEmissionState {
+            taqueria_weight: legacy.taqueria_weight,
+            reward_debt: legacy.reward_debt,
+            pending: legacy.pending,
+            last_poc_hash: legacy.last_poc_hash,
+            poc_count: 0,
+        })
+    }
+
+    //Retargeted up to the current block, not persisted
+    pub fn get_poc_difficulty_value(&self) -> Result<SchemaPocDifficultyState> {
+        let now_block: u128 = self.height().into();
+        let bytes = (*self.get_poc_difficulty_pointer().get()).clone();
+
+        let mut state = if bytes.is_empty() {
+            default_poc_difficulty(now_block)
+        } else {
+            decode_from_vec!(bytes, SchemaPocDifficultyState)?
+        };
 
+        retarget_poc_difficulty(&mut state, now_block);
+
+        Ok(state)
+    }
+
+    pub fn set_poc_difficulty(&self, state: &SchemaPocDifficultyState) -> Result<()> {
+        self.get_poc_difficulty_pointer().set(Arc::new(
+            borsh::to_vec(state).context("TORTILLA: failed to encode poc difficulty")?,
+        ));
         Ok(())
     }
 
-    pub fn calc_unclaimed_tortilla(&self, taqueria: &SchemaAlkaneId) -> Result<u128> {
+    pub fn set_taqueria_emission_state(
+        &self,
+        taqueria: &SchemaAlkaneId,
+        state: &SchemaTaqueriaEmissionState,
+    ) -> Result<()> {
+        self.get_taqueria_emission_state_pointer(taqueria)?
+            .set(Arc::new(borsh::to_vec(state).context(
+                "TORTILLA: failed to encode taqueria emission state",
+            )?));
+        Ok(())
+    }
+
+    //Stores the current block hash and, from the header, the previous one. Any call that accrues
+    //emissions at height h or h + 1 makes block h's hash available to RevealBet afterwards
+    pub fn record_block_hashes(&self) -> Result<()> {
         let now_block: u128 = self.height().into();
+        let header = self
+            .block_header()
+            .context("TORTILLA: failed to get block header")?;
+
+        let mut ptr_current = self.get_block_hash_pointer(now_block);
+        if ptr_current.get().is_empty() {
+            ptr_current.set(Arc::new(
+                header.block_hash().to_raw_hash().to_byte_array().to_vec(),
+            ));
+        }
+
+        if let Some(previous_block) = now_block.checked_sub(1) {
+            let mut ptr_previous = self.get_block_hash_pointer(previous_block);
+            if ptr_previous.get().is_empty() {
+                ptr_previous.set(Arc::new(
+                    header.prev_blockhash.to_raw_hash().to_byte_array().to_vec(),
+                ));
+            }
+        }
 
-        let global_bytes = (*self.get_global_emission_state_pointer().get()).clone();
-        let global: SchemaGlobalEmissionState =
-            decode_from_vec!(global_bytes, SchemaGlobalEmissionState)?;
+        Ok(())
+    }
 
-        let blocks = now_block
-            .checked_sub(global.last_updated_block)
-            .context("TORTILLA: block underflow in calc_unclaimed")?;
+    pub fn get_recorded_block_hash(&self, height: u128) -> Option<Vec<u8>> {
+        let bytes = (*self.get_block_hash_pointer(height).get()).clone();
 
-        let acc_now = if global.total_weight > 0 && blocks > 0 {
-            global.acc_reward_per_weight + (blocks * TORTILLA_PER_BLOCK) / global.total_weight
+        if bytes.is_empty() {
+            None
         } else {
-            global.acc_reward_per_weight
+            Some(bytes)
+        }
+    }
+
+    pub fn get_bet_commit_value(
+        &self,
+        taqueria: &SchemaAlkaneId,
+    ) -> Result<Option<SchemaBetCommit>> {
+        let bytes = (*self.get_bet_commit_pointer(taqueria)?.get()).clone();
+
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(decode_from_vec!(bytes, SchemaBetCommit)?))
+    }
+
+    pub fn set_bet_commit(
+        &self,
+        taqueria: &SchemaAlkaneId,
+        commit: &SchemaBetCommit,
+    ) -> Result<()> {
+        self.get_bet_commit_pointer(taqueria)?.set(Arc::new(
+            borsh::to_vec(commit).context("TORTILLA: failed to encode bet commit")?,
+        ));
+        Ok(())
+    }
+
+    pub fn clear_bet_commit(&self, taqueria: &SchemaAlkaneId) -> Result<()> {
+        self.get_bet_commit_pointer(taqueria)?
+            .set(Arc::new(Vec::new()));
+        Ok(())
+    }
+
+    pub fn get_taqueria_bet_stats_value(
+        &self,
+        taqueria: &SchemaAlkaneId,
+    ) -> Result<SchemaTaqueriaBetStats> {
+        let bytes = (*self.get_bet_stats_pointer(taqueria)?.get()).clone();
+
+        if bytes.is_empty() {
+            return Ok(SchemaTaqueriaBetStats::default());
+        }
+
+        decode_from_vec!(bytes, SchemaTaqueriaBetStats)
+    }
+
+    pub fn get_global_bet_stats_value(&self) -> Result<SchemaGlobalBetStats> {
+        let bytes = (*self.get_global_bet_stats_pointer().get()).clone();
+
+        if bytes.is_empty() {
+            return Ok(SchemaGlobalBetStats::default());
+        }
+
+        decode_from_vec!(bytes, SchemaGlobalBetStats)
+    }
+
+    //Bet number n (0 based) of a taqueria, if it is still in the ring buffer
+    pub fn get_bet_history_entry(
+        &self,
+        taqueria: &SchemaAlkaneId,
+        bet: u64,
+    ) -> Result<SchemaBetHistoryEntry> {
+        let slot = (bet % BET_HISTORY_SIZE) as u32;
+        let bytes = (*self
+            .get_bet_history_pointer(taqueria)?
+            .select_index(slot)
+            .get())
+        .clone();
+
+        decode_from_vec!(bytes, SchemaBetHistoryEntry)
+    }
+
+    pub fn record_bet(
+        &self,
+        taqueria: &SchemaAlkaneId,
+        entry: SchemaBetHistoryEntry,
+    ) -> Result<()> {
+        let mut stats = self.get_taqueria_bet_stats_value(taqueria)?;
+        let mut global = self.get_global_bet_stats_value()?;
+
+        let slot = (stats.bets % BET_HISTORY_SIZE) as u32;
+        self.get_bet_history_pointer(taqueria)?
+            .select_index(slot)
+            .set(Arc::new(
+                borsh::to_vec(&entry).context("TORTILLA: failed to encode bet history entry")?,
+            ));
+
+        stats.bets = stats.bets.saturating_add(1);
+        if entry.won_amount > 0 {
+            stats.wins = stats.wins.saturating_add(1);
+        }
+        stats.total_staked = stats.total_staked.saturating_add(entry.stake_amount);
+        stats.total_won = stats.total_won.saturating_add(entry.won_amount);
+        stats.total_lost = stats.total_lost.saturating_add(entry.lost_amount);
+
+        global.bets = global.bets.saturating_add(1);
+        global.total_created = global.total_created.saturating_add(entry.won_amount);
+        global.total_burned = global.total_burned.saturating_add(entry.lost_amount);
+
+        self.get_bet_stats_pointer(taqueria)?.set(Arc::new(
+            borsh::to_vec(&stats).context("TORTILLA: failed to encode bet stats")?,
+        ));
+        self.get_global_bet_stats_pointer().set(Arc::new(
+            borsh::to_vec(&global).context("TORTILLA: failed to encode global bet stats")?,
+        ));
+
+        Ok(())
+    }
+
+    pub fn get_referral_value(&self, taqueria: &SchemaAlkaneId) -> Result<Option<SchemaReferral>> {
+        let bytes = (*self.get_referral_pointer(taqueria)?.get()).clone();
+
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(decode_from_vec!(bytes, SchemaReferral)?))
+    }
+
+    //Links a freshly registered taqueria to its referrer and credits the referrer the current bonus,
+    //or whatever is left of REFERRAL_POOL if that is less
+    pub fn record_referral(
+        &self,
+        taqueria: &SchemaAlkaneId,
+        referrer: &SchemaAlkaneId,
+    ) -> Result<()> {
+        let mut ptr_bonus_paid = self.get_referral_bonus_paid_pointer();
+        let bonus_paid = ptr_bonus_paid.get_value::<u128>();
+
+        let bonus = self
+            .get_params_value()?
+            .referral_bonus
+            .min(REFERRAL_POOL.saturating_sub(bonus_paid));
+        ptr_bonus_paid.set_value::<u128>(bonus_paid + bonus);
+
+        let referral = SchemaReferral {
+            referrer: *referrer,
+            block: self.height().into(),
+            bonus,
         };
 
-        let taq_ptr = self
-            .get_taqueria_emission_state_pointer(taqueria)
-            .context("TORTILLA: taqueria emission state not found")?;
+        self.get_referral_pointer(taqueria)?.set(Arc::new(
+            borsh::to_vec(&referral).context("TORTILLA: failed to encode referral")?,
+        ));
+        self.get_referred_list_pointer(referrer)?.append(Arc::new(
+            borsh::to_vec(taqueria).context("TORTILLA: failed to encode taqueria id")?,
+        ));
+
+        let mut ptr_bonus_total = self.get_referral_bonus_total_pointer(referrer)?;
+        ptr_bonus_total
+            .set_value::<u128>(ptr_bonus_total.get_value::<u128>().saturating_add(bonus));
+
+        //pending sits outside the accumulator, so it can be credited without settling
+        let mut referrer_state = self.get_taqueria_emission_state_value(referrer)?;
+        referrer_state.pending = referrer_state
+            .pending
+            .checked_add(bonus)
+            .context("TORTILLA: pending overflow")?;
+        self.set_taqueria_emission_state(referrer, &referrer_state)?;
+
+        Ok(())
+    }
+
+    pub fn update_global(&self) -> Result<()> {
+        let now_block: u128 = self.height().into();
+
+        self.record_block_hashes()?;
+
+        let mut state = self
+            .get_global_emission_state_value()
+            .context("TORTILLA: failed to decode global emission state")?;
+
+        //Already updated for this block
+        if now_block == state.last_updated_block {
+            return Ok(());
+        }
+
+        let schedule = self.get_params_value()?.emission_schedule;
+        accrue_global_emission(&mut state, &schedule, now_block)?;
+
+        self.set_global_emission_state(&state)
+    }
+
+    //Moves everything the taqueria earned up to this block into pending and resets its debt.
+    //Must run before anything that reads pending or changes weight
+    pub fn settle_taqueria(
+        &self,
+        taqueria: &SchemaAlkaneId,
+    ) -> Result<SchemaTaqueriaEmissionState> {
+        self.update_global()?;
+
+        let global = self.get_global_emission_state_value()?;
+        let mut state = self.get_taqueria_emission_state_value(taqueria)?;
+
+        let accrued = accrued_reward(state.taqueria_weight, global.acc_reward_per_weight)?;
+        let earned = accrued
+            .checked_sub(state.reward_debt)
+            .context("TORTILLA: underflow settling taqueria")?;
 
-        let taqueria_emission_state_bytes = (*taq_ptr.get()).clone();
+        state.pending = state.pending.saturating_add(earned);
+        state.reward_debt = accrued;
 
-        let taq: SchemaTaqueriaEmissionState =
-            decode_from_vec!(taqueria_emission_state_bytes, SchemaTaqueriaEmissionState)?;
+        self.set_taqueria_emission_state(taqueria, &state)?;
 
-        // earned_since_last = weight * acc_now − reward_debt
-        let earned_since_last = taq
-            .taqueria_weight
-            .checked_mul(acc_now)
-            .context("TORTILLA: mul ovf in calc_unclaimed")?
+        Ok(state)
+    }
+
+    //Settles the taqueria and empties its pending, returning what was in it
+    pub fn take_pending(&self, taqueria: &SchemaAlkaneId) -> Result<u128> {
+        let mut state = self.settle_taqueria(taqueria)?;
+        let pending = state.pending;
+
+        state.pending = 0;
+        self.set_taqueria_emission_state(taqueria, &state)?;
+
+        Ok(pending)
+    }
+
+    //Settles at the old weight first so nothing accrued so far is repriced at the new one
+    pub fn set_taqueria_weight(
+        &self,
+        taqueria: &SchemaAlkaneId,
+        weight: u128,
+    ) -> Result<SchemaTaqueriaEmissionState> {
+        let mut state = self.settle_taqueria(taqueria)?;
+        let mut global = self.get_global_emission_state_value()?;
+
+        global.total_weight = global
+            .total_weight
+            .checked_sub(state.taqueria_weight)
+            .and_then(|total| total.checked_add(weight))
+            .context("TORTILLA: total weight overflow")?;
+
+        state.taqueria_weight = weight;
+        state.reward_debt = accrued_reward(weight, global.acc_reward_per_weight)?;
+
+        self.set_global_emission_state(&global)?;
+        self.set_taqueria_emission_state(taqueria, &state)?;
+        self.update_leaderboard(taqueria, weight)?;
+
+        Ok(state)
+    }
+
+    pub fn create_taqueria_deps(&self, taqueria: &SchemaAlkaneId) -> Result<()> {
+        let catalogue = self.get_upgrade_catalogue_value()?;
+        let starter = get_upgrade_by_id(&catalogue, STARTER_UPGRADE_ID)?;
+
+        //Every other upgrade defaults to zero owned at base cost, so only the free starter unit is written
+        self.set_taqueria_upgrade(
+            taqueria,
+            &SchemaUserUpgradesEntry {
+                id: starter.id,
+                amount: 1u128,
+                next_price: starter.base_cost,
+            },
+        )?;
+
+        //Accrue up to now at the old total weight, then start the new taqueria's debt at the current
+        //accumulator so it only earns from this block on
+        self.update_global()?;
+
+        let mut global = self.get_global_emission_state_value()?;
+
+        let default_user_emission_state_view = SchemaTaqueriaEmissionState {
+            taqueria_weight: starter.weight,
+            reward_debt: accrued_reward(starter.weight, global.acc_reward_per_weight)?,
+            pending: 0u128,
+            last_poc_hash: Vec::new(),
+            poc_count: 0,
+        };
+
+        self.set_taqueria_emission_state(taqueria, &default_user_emission_state_view)?;
+
+        global.total_weight = global
+            .total_weight
+            .checked_add(starter.weight)
+            .context("TORTILLA: total weight overflow")?;
+
+        self.update_leaderboard(taqueria, starter.weight)?;
+        self.set_global_emission_state(&global)
+    }
+
+    //Global state as it would be after update_global at the current block, without writing it
+    pub fn get_projected_global_emission_state(&self) -> Result<SchemaGlobalEmissionState> {
+        let mut global = self.get_global_emission_state_value()?;
+        let schedule = self.get_params_value()?.emission_schedule;
+
+        accrue_global_emission(&mut global, &schedule, self.height().into())?;
+
+        Ok(global)
+    }
+
+    pub fn calc_unclaimed_tortilla(&self, taqueria: &SchemaAlkaneId) -> Result<u128> {
+        let global = self.get_projected_global_emission_state()?;
+        let taq = self.get_taqueria_emission_state_value(taqueria)?;
+
+        // earned_since_last = weight * acc_now / ACC_PRECISION − reward_debt
+        let earned_since_last = accrued_reward(taq.taqueria_weight, global.acc_reward_per_weight)?
             .checked_sub(taq.reward_debt)
             .context("TORTILLA: underflow in calc_unclaimed")?;
 
         Ok(taq.pending.saturating_add(earned_since_last))
     }
 
+    pub fn get_global_salsa_state_value(&self) -> Result<SchemaGlobalSalsaState> {
+        let bytes = (*self.get_global_salsa_state_pointer().get()).clone();
+
+        if let Ok(state) = SchemaGlobalSalsaState::try_from_slice(&bytes) {
+            return Ok(state);
+        }
+
+        //Pre escrow deployments already credited the round leader's pending, so the in flight round carries no prize
+        let legacy = decode_from_vec!(bytes, SchemaLegacyGlobalSalsaState)?;
+
+        Ok(SchemaGlobalSalsaState {
+            current_block: legacy.current_block,
+            best_hash: legacy.best_hash,
+            best_hash_owner: legacy.best_hash_owner,
+            round: legacy.current_block / SALSA_ROUND_BLOCKS,
+            prize: 0u128,
+        })
+    }
+
+    pub fn set_global_salsa_state(&self, state: &SchemaGlobalSalsaState) -> Result<()> {
+        self.get_global_salsa_state_pointer().set(Arc::new(
+            borsh::to_vec(state).context("TORTILLA: failed to encode salsa state")?,
+        ));
+        Ok(())
+    }
+
+    pub fn get_salsa_round_value(&self, round: u128) -> Result<Option<SchemaSalsaRound>> {
+        let bytes = (*self.get_salsa_round_pointer(round).get()).clone();
+
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(decode_from_vec!(bytes, SchemaSalsaRound)?))
+    }
+
+    pub fn set_salsa_round(&self, round: &SchemaSalsaRound) -> Result<()> {
+        self.get_salsa_round_pointer(round.round).set(Arc::new(
+            borsh::to_vec(round).context("TORTILLA: failed to encode salsa round")?,
+        ));
+        Ok(())
+    }
+
+    pub fn current_salsa_round(&self) -> u128 {
+        u128::from(self.height()) / SALSA_ROUND_BLOCKS
+    }
+
+    //Archives the tracked round once the chain has moved past it and opens the current one with a
+    //fresh prize snapshot. Rounds are only closed lazily, by the first salsa action after they end
+    pub fn roll_salsa_round(&self) -> Result<SchemaGlobalSalsaState> {
+        let mut salsa = self.get_global_salsa_state_value()?;
+        let current_round = self.current_salsa_round();
+
+        if salsa.round == current_round {
+            return Ok(salsa);
+        }
+
+        if !salsa.best_hash_owner.is_empty() {
+            let winner_bytes = salsa.best_hash_owner.clone();
+
+            self.set_salsa_round(&SchemaSalsaRound {
+                round: salsa.round,
+                round_block: salsa.current_block,
+                winning_hash: salsa.best_hash.clone(),
+                winner: decode_from_vec!(winner_bytes, SchemaAlkaneId)?,
+                prize: salsa.prize,
+                claimed: false,
+            })?;
+        }
+
+        //conveniently, the clockin block is at an offset of 5 from modulo 144. This means by doing this
+        //People, who are already online waiting for clockin, will be able to participate in the salsa block
+        salsa = SchemaGlobalSalsaState {
+            current_block: current_round
+                .checked_mul(SALSA_ROUND_BLOCKS)
+                .context("TORTILLA: salsa round overflow")?,
+            best_hash: Vec::new(),
+            best_hash_owner: Vec::new(),
+            round: current_round,
+            prize: self.get_params_value()?.salsa_block_reward,
+        };
+
+        self.set_global_salsa_state(&salsa)?;
+
+        Ok(salsa)
+    }
+
     pub fn update_global_salsa(&self, taqueria: &SchemaAlkaneId) -> Result<()> {
-        let current_height = self.height() as u128;
         let tx_bytes = self
             .get_serialized_transaction()?
             .compute_txid()
@@ -222,18 +935,7 @@ impl Tortilla {
 
         let block_hash_bytes: Vec<u8> = self.blockhash()?;
 
-        let mut ptr_salsa_global_state = self.get_global_salsa_state_pointer();
-        let global_salsa_state_bytes = (*ptr_salsa_global_state.get()).clone();
-        let mut salsa: SchemaGlobalSalsaState =
-            decode_from_vec!(global_salsa_state_bytes, SchemaGlobalSalsaState)?;
-
-        //conveniently, the clockin block is at an offset of 5 from modulo 144. This means by doing this
-        //People, who are already online waiting for clockin, will be able to participate in the salsa block
-        if current_height % 144 == 0 && salsa.current_block != current_height {
-            salsa.current_block = current_height;
-            salsa.best_hash.clear();
-            salsa.best_hash_owner.clear();
-        }
+        let mut salsa = self.roll_salsa_round()?;
 
         let mut txid = [0u8; 32];
         txid.copy_from_slice(&tx_bytes[..32]);
@@ -248,41 +950,14 @@ impl Tortilla {
         };
         let candidate = sha2::Sha256::digest(&xor).to_vec();
 
+        //Only the leader changes here, the escrowed prize is paid once the round is over
         let beats_current = salsa.best_hash.is_empty() || candidate < salsa.best_hash;
         if beats_current {
-            {
-                let mut ptr_taqueria_emission_state =
-                    self.get_taqueria_emission_state_pointer(taqueria)?;
-                let taqueria_emission_state_bytes = (*ptr_taqueria_emission_state.get()).clone();
-                let mut taq: SchemaTaqueriaEmissionState =
-                    decode_from_vec!(taqueria_emission_state_bytes, SchemaTaqueriaEmissionState)?;
-                taq.pending = taq.pending.saturating_add(SALSA_BLOCK_REWARD);
-                ptr_taqueria_emission_state.set(Arc::new(borsh::to_vec(&taq)?));
-            }
-
-            if !salsa.best_hash_owner.is_empty() {
-                let best_hash_clone = salsa.best_hash_owner.clone();
-                let prev_owner: SchemaAlkaneId = decode_from_vec!(best_hash_clone, SchemaAlkaneId)?;
-                let mut ptr_prev_taqueria_emission_state =
-                    self.get_taqueria_emission_state_pointer(&prev_owner)?;
-
-                let prev_taqueria_emission_state_bytes =
-                    (*ptr_prev_taqueria_emission_state.get()).clone();
-
-                let mut prev: SchemaTaqueriaEmissionState = decode_from_vec!(
-                    prev_taqueria_emission_state_bytes,
-                    SchemaTaqueriaEmissionState
-                )?;
-                prev.pending = prev.pending.saturating_sub(SALSA_BLOCK_REWARD);
-                ptr_prev_taqueria_emission_state.set(Arc::new(borsh::to_vec(&prev)?));
-            }
-
             salsa.best_hash = candidate.to_vec();
             salsa.best_hash_owner = borsh::to_vec(&taqueria)?;
+            self.set_global_salsa_state(&salsa)?;
         }
 
-        ptr_salsa_global_state.set(Arc::new(borsh::to_vec(&salsa)?));
-
         Ok(())
     }
 
@@ -292,39 +967,25 @@ impl Tortilla {
         nonce_found_poc: u128,
     ) -> Result<Vec<u8>> {
         // ─────────────────────────────── 1. load emission state for this taqueria
-        let mut ptr = self
-            .get_taqueria_emission_state_pointer(taqueria)
-            .context("TORTILLA: taqueria emission state not found")?;
-
-        let taqueria_emission_state_bytes = (*ptr.get()).clone();
-
-        let mut state: SchemaTaqueriaEmissionState =
-            decode_from_vec!(taqueria_emission_state_bytes, SchemaTaqueriaEmissionState)?;
-
-        // ─────────────────────────────── 2. build input buffer
-        let mut buf = Vec::with_capacity(32 + 16 + state.last_poc_hash.len());
-
-        // 2a. alkane_id → Borsh bytes
-        buf.extend(borsh::to_vec(taqueria)?);
-
-        // 2b. nonce → 16‑byte big‑endian
-        buf.extend_from_slice(&nonce_found_poc.to_be_bytes());
+        let mut state = self.get_taqueria_emission_state_value(taqueria)?;
+        let mut difficulty = self.get_poc_difficulty_value()?;
 
-        // 2c. previous hash (could be empty on first run)
-        buf.extend_from_slice(&state.last_poc_hash);
-
-        // ─────────────────────────────── 3. compute SHA‑256
-        let new_hash = Sha256::digest(&buf);
-
-        // ─────────────────────────────── 4. leading‑byte rule
-        ensure!(
-            new_hash[0] == 0x00,
-            "TORTILLA: new PoC hash does not start with 0x00"
-        );
+        // ─────────────────────────────── 2‑4. hash and leading‑zero‑bits rule, see game::poc
+        let new_hash = proof_of_click_hash(
+            taqueria,
+            nonce_found_poc,
+            &state.last_poc_hash,
+            difficulty.difficulty_bits,
+        )?;
 
         // ─────────────────────────────── 5. persist & return
         state.last_poc_hash = new_hash.to_vec();
-        ptr.set(Arc::new(borsh::to_vec(&state)?));
+        state.poc_count = state.poc_count.saturating_add(1);
+        self.set_taqueria_emission_state(taqueria, &state)?;
+
+        difficulty.window_poc_count = difficulty.window_poc_count.saturating_add(1);
+        difficulty.total_poc_count = difficulty.total_poc_count.saturating_add(1);
+        self.set_poc_difficulty(&difficulty)?;
 
         Ok(state.last_poc_hash.clone())
     }
@@ -1,50 +1,519 @@
-use crate::game::consts::{UpgradeSheetPriceEntry, UPGRADES};
-use crate::game::schemas::{SchemaUserUpgradesEntry, SchemaUserUpgradesView};
-use anyhow::{anyhow, Result};
-
-pub fn get_upgrade_by_id(id: u8) -> Result<&'static UpgradeSheetPriceEntry> {
-    match id {
-        0 => Ok(&UPGRADES.taquero),
-        1 => Ok(&UPGRADES.salsa_bar),
-        2 => Ok(&UPGRADES.tortilla_tree),
-        3 => Ok(&UPGRADES.tortilla_factory),
-        4 => Ok(&UPGRADES.taco_submarine),
-        5 => Ok(&UPGRADES.taco_pyramid),
-        6 => Ok(&UPGRADES.tortilla_spaceship),
-        7 => Ok(&UPGRADES.satoshi_tacomoto),
-        _ => Err(anyhow!("TORTILLA: invalid upgrade ID {id}")),
-    }
-}
-pub fn get_upgrade_entry_by_id_mut<'a>(
-    upgrades: &'a mut SchemaUserUpgradesView,
-    id: u8,
-) -> Result<&'a mut SchemaUserUpgradesEntry> {
-    match id {
-        0 => Ok(&mut upgrades.taquero),
-        1 => Ok(&mut upgrades.salsa_bar),
-        2 => Ok(&mut upgrades.tortilla_tree),
-        3 => Ok(&mut upgrades.tortilla_factory),
-        4 => Ok(&mut upgrades.taco_submarine),
-        5 => Ok(&mut upgrades.taco_pyramid),
-        6 => Ok(&mut upgrades.tortilla_spaceship),
-        7 => Ok(&mut upgrades.satoshi_tacomoto),
-        _ => Err(anyhow!("TORTILLA: invalid upgrade ID {id}")),
-    }
-}
-
-pub fn get_upgrade_entry_by_id<'a>(
-    upgrades: &'a SchemaUserUpgradesView,
+use crate::consts::BPS_DENOMINATOR;
+use crate::game::consts::{
+    DEFAULT_UPGRADES, LEADERBOARD_SIZE, MAX_TAQUERIA_NAME_LENGTH, MAX_TAQUERIA_SYMBOL_LENGTH,
+    MAX_UPGRADES_PER_PURCHASE, STARTER_UPGRADE_ID,
+};
+use crate::game::schemas::{
+    SchemaCompoundPurchase, SchemaLeaderboard, SchemaLeaderboardStoredEntry,
+    SchemaUpgradeCatalogue, SchemaUpgradeCatalogueEntry, SchemaUserUpgradesEntry,
+};
+use crate::schemas::SchemaAlkaneId;
+use anyhow::{anyhow, ensure, Context, Result};
+
+pub fn default_upgrade_catalogue() -> SchemaUpgradeCatalogue {
+    SchemaUpgradeCatalogue {
+        upgrades: DEFAULT_UPGRADES
+            .iter()
+            .map(|upgrade| SchemaUpgradeCatalogueEntry {
+                id: upgrade.id,
+                name: upgrade.name.to_string(),
+                base_cost: upgrade.base_cost,
+                weight: upgrade.weight,
+                price_growth_numerator: upgrade.price_growth_numerator,
+                price_growth_denominator: upgrade.price_growth_denominator,
+            })
+            .collect(),
+    }
+}
+
+//Catalogue ids are positional (id == index), so lookups stay O(1) no matter how many tiers exist
+pub fn get_upgrade_by_id(
+    catalogue: &SchemaUpgradeCatalogue,
     id: u8,
-) -> Result<&'a SchemaUserUpgradesEntry> {
-    match id {
-        0 => Ok(&upgrades.taquero),
-        1 => Ok(&upgrades.salsa_bar),
-        2 => Ok(&upgrades.tortilla_tree),
-        3 => Ok(&upgrades.tortilla_factory),
-        4 => Ok(&upgrades.taco_submarine),
-        5 => Ok(&upgrades.taco_pyramid),
-        6 => Ok(&upgrades.tortilla_spaceship),
-        7 => Ok(&upgrades.satoshi_tacomoto),
-        _ => Err(anyhow!("TORTILLA: invalid upgrade ID {id}")),
+) -> Result<&SchemaUpgradeCatalogueEntry> {
+    catalogue
+        .upgrades
+        .get(id as usize)
+        .filter(|upgrade| upgrade.id == id)
+        .ok_or_else(|| anyhow!("TORTILLA: invalid upgrade ID {id}"))
+}
+
+//Units handed out for free at register, they were never paid for so they cant be sold back
+pub fn free_upgrade_units(upgrade: &SchemaUpgradeCatalogueEntry) -> u128 {
+    if upgrade.id == STARTER_UPGRADE_ID {
+        1u128
+    } else {
+        0u128
+    }
+}
+
+//Price the last unit was bought at, given the next_price it left behind. With numerator >= denominator
+//next_upgrade_price never maps two prices to the same one, so ceil(next * den / num) is exactly the
+//price it was computed from, floor included. Split as (next / num) * den + ceil((next % num) * den / num)
+//so it cant overflow where the price itself fits
+pub fn previous_upgrade_price(
+    upgrade: &SchemaUpgradeCatalogueEntry,
+    next_price: u128,
+) -> Result<u128> {
+    let numerator = upgrade.price_growth_numerator;
+    let denominator = upgrade.price_growth_denominator;
+
+    let whole = (next_price
+        .checked_div(numerator)
+        .context("TORTILLA: upgrade has a zero price growth numerator")?)
+    .checked_mul(denominator)
+    .context("TORTILLA: price overflow")?;
+    let fraction = (next_price % numerator)
+        .checked_mul(denominator)
+        .context("TORTILLA: price overflow")?
+        .div_ceil(numerator);
+
+    whole
+        .checked_add(fraction)
+        .context("TORTILLA: price overflow")
+}
+
+//Share of the current per block emission one more unit of an upgrade would earn, as shown by GetAvailableUpgrades
+pub fn upgrade_current_emission(
+    weight: u128,
+    tortilla_per_block: u128,
+    total_weight: u128,
+) -> u128 {
+    weight
+        .saturating_mul(tortilla_per_block)
+        .checked_div(total_weight)
+        // Nobody has any weight yet; one unit of this upgrade captures the full emission.
+        .unwrap_or(tortilla_per_block)
+}
+
+//Names are ASCII letters, digits, spaces, '-' and '_', without leading or trailing spaces
+pub fn validate_taqueria_name(name: &str) -> Result<()> {
+    ensure!(
+        !name.is_empty() && name.len() <= MAX_TAQUERIA_NAME_LENGTH,
+        "TORTILLA: taqueria name must be 1 to {MAX_TAQUERIA_NAME_LENGTH} characters"
+    );
+    ensure!(
+        name.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '_'),
+        "TORTILLA: taqueria name may only use letters, digits, spaces, '-' and '_'"
+    );
+    ensure!(
+        name.trim() == name,
+        "TORTILLA: taqueria name cannot start or end with a space"
+    );
+
+    Ok(())
+}
+
+//Symbols are uppercase ASCII letters and digits
+pub fn validate_taqueria_symbol(symbol: &str) -> Result<()> {
+    ensure!(
+        !symbol.is_empty() && symbol.len() <= MAX_TAQUERIA_SYMBOL_LENGTH,
+        "TORTILLA: taqueria symbol must be 1 to {MAX_TAQUERIA_SYMBOL_LENGTH} characters"
+    );
+    ensure!(
+        symbol
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()),
+        "TORTILLA: taqueria symbol may only use uppercase letters and digits"
+    );
+
+    Ok(())
+}
+
+pub fn next_upgrade_price(upgrade: &SchemaUpgradeCatalogueEntry, price: u128) -> Result<u128> {
+    price
+        .checked_mul(upgrade.price_growth_numerator)
+        .context("TORTILLA: price overflow")?
+        .checked_div(upgrade.price_growth_denominator)
+        .context("TORTILLA: upgrade has a zero price growth denominator")
+}
+
+//Walks the price curve one unit at a time so the total is exactly what single buys would have cost,
+//stopping at the first unit budget cant cover. Returns (bought, spent)
+pub fn buy_upgrade_units(
+    upgrade: &SchemaUpgradeCatalogueEntry,
+    entry: &mut SchemaUserUpgradesEntry,
+    quantity: u32,
+    budget: u128,
+) -> Result<(u128, u128)> {
+    ensure!(
+        quantity > 0 && quantity <= MAX_UPGRADES_PER_PURCHASE,
+        "TORTILLA: quantity must be between 1 and {MAX_UPGRADES_PER_PURCHASE}"
+    );
+
+    let mut bought = 0u128;
+    let mut spent = 0u128;
+
+    while bought < u128::from(quantity) {
+        let spent_with_next = match spent.checked_add(entry.next_price) {
+            Some(total) if total <= budget => total,
+            _ => break,
+        };
+
+        spent = spent_with_next;
+        bought += 1;
+        entry.amount = entry
+            .amount
+            .checked_add(1u128)
+            .context("TORTILLA: upgrade amount overflow")?;
+        entry.next_price = next_upgrade_price(upgrade, entry.next_price)?;
+    }
+
+    Ok((bought, spent))
+}
+
+//The unit being sold was the last one bought, so its price becomes the next price again. Returns the
+//refund, sell_refund_bps of that price
+pub fn sell_upgrade_unit(
+    upgrade: &SchemaUpgradeCatalogueEntry,
+    entry: &mut SchemaUserUpgradesEntry,
+    sell_refund_bps: u16,
+) -> Result<u128> {
+    let paid_units = entry.amount.saturating_sub(free_upgrade_units(upgrade));
+    ensure!(
+        paid_units > 0,
+        "TORTILLA: taqueria has no purchased {} to sell",
+        upgrade.name
+    );
+
+    let last_price = previous_upgrade_price(upgrade, entry.next_price)?;
+    entry.amount -= 1;
+    entry.next_price = last_price;
+
+    Ok(last_price
+        .checked_mul(sell_refund_bps.into())
+        .context("TORTILLA: overflow computing sell refund")?
+        / BPS_DENOMINATOR)
+}
+
+pub struct UpgradeCandidate<'a> {
+    pub upgrade: &'a SchemaUpgradeCatalogueEntry,
+    pub entry: SchemaUserUpgradesEntry,
+    pub emission: u128, //current emission of one more unit
+    pub purchase: SchemaCompoundPurchase,
+}
+
+//Spends budget one unit at a time on the candidate with the best emission per cost, at most
+//MAX_UPGRADES_PER_PURCHASE units. Returns (added weight, spent), the rest of budget is the caller's
+pub fn buy_upgrades_greedily(
+    candidates: &mut [UpgradeCandidate],
+    budget: u128,
+) -> Result<(u128, u128)> {
+    let mut budget = budget;
+    let mut added_weight = 0u128;
+    let mut spent = 0u128;
+
+    for _ in 0..MAX_UPGRADES_PER_PURCHASE {
+        // a / b > c / d  <=>  a * d > c * b, ties go to the cheaper unit
+        let best = candidates
+            .iter_mut()
+            .filter(|candidate| candidate.entry.next_price <= budget)
+            .max_by(|a, b| {
+                a.emission
+                    .saturating_mul(b.entry.next_price)
+                    .cmp(&b.emission.saturating_mul(a.entry.next_price))
+                    .then(b.entry.next_price.cmp(&a.entry.next_price))
+            });
+
+        let Some(candidate) = best else {
+            break;
+        };
+
+        let price = candidate.entry.next_price;
+        budget -= price;
+        spent += price;
+
+        candidate.purchase.bought += 1;
+        candidate.purchase.spent += price;
+        candidate.entry.amount = candidate
+            .entry
+            .amount
+            .checked_add(1u128)
+            .context("TORTILLA: upgrade amount overflow")?;
+        candidate.entry.next_price = next_upgrade_price(candidate.upgrade, price)?;
+        added_weight = added_weight
+            .checked_add(candidate.upgrade.weight)
+            .context("TORTILLA: upgrade weight overflow")?;
+    }
+
+    Ok((added_weight, spent))
+}
+
+//Re-ranks one taqueria after its weight changed, ties keep the earlier holder ahead. A board entry
+//whose weight drops keeps its place in the board and is only pushed off by a heavier taqueria, so a
+//light newcomer can never take a slot from taquerias that were already ranked
+pub fn rank_on_leaderboard(
+    leaderboard: &mut SchemaLeaderboard,
+    taqueria: &SchemaAlkaneId,
+    weight: u128,
+) {
+    leaderboard
+        .entries
+        .retain(|entry| entry.taqueria != *taqueria);
+
+    let position = leaderboard
+        .entries
+        .iter()
+        .position(|entry| entry.weight < weight)
+        .unwrap_or(leaderboard.entries.len());
+
+    if position < LEADERBOARD_SIZE {
+        leaderboard.entries.insert(
+            position,
+            SchemaLeaderboardStoredEntry {
+                taqueria: *taqueria,
+                weight,
+            },
+        );
+        leaderboard.entries.truncate(LEADERBOARD_SIZE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn upgrade(numerator: u128, denominator: u128) -> SchemaUpgradeCatalogueEntry {
+        SchemaUpgradeCatalogueEntry {
+            id: 1,
+            name: "Salsa Bar".to_string(),
+            base_cost: 1_000_001,
+            weight: 1,
+            price_growth_numerator: numerator,
+            price_growth_denominator: denominator,
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn previous_upgrade_price_undoes_every_floored_buy() {
+        for (numerator, denominator) in [(3, 2), (7, 5), (1, 1), (1_000_003, 1_000_000)] {
+            let upgrade = upgrade(numerator, denominator);
+            let mut price = upgrade.base_cost;
+
+            for _ in 0..40 {
+                let next = next_upgrade_price(&upgrade, price).unwrap();
+                assert_eq!(previous_upgrade_price(&upgrade, next).unwrap(), price);
+                price = next;
+            }
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn previous_upgrade_price_does_not_overflow_near_the_limit() {
+        //u128::MAX is a multiple of 3, and next_price * 2 would overflow if computed directly
+        assert_eq!(
+            previous_upgrade_price(&upgrade(3, 2), u128::MAX).unwrap(),
+            u128::MAX / 3 * 2
+        );
+    }
+
+    fn taqueria(tx: u64) -> SchemaAlkaneId {
+        SchemaAlkaneId { block: 2, tx }
+    }
+
+    //Taqueria i weighs 1_000 + i, so the board is sorted from the last one down
+    fn full_board() -> SchemaLeaderboard {
+        let mut leaderboard = SchemaLeaderboard::default();
+        for i in 0..LEADERBOARD_SIZE as u64 {
+            rank_on_leaderboard(&mut leaderboard, &taqueria(i), 1_000 + i as u128);
+        }
+        leaderboard
+    }
+
+    #[wasm_bindgen_test]
+    fn dropped_entry_keeps_its_slot_from_a_lighter_newcomer() {
+        let mut leaderboard = full_board();
+
+        //The heaviest taqueria sells down to 2, then a fresh weight 1 registration arrives
+        let dropped = taqueria(LEADERBOARD_SIZE as u64 - 1);
+        rank_on_leaderboard(&mut leaderboard, &dropped, 2);
+        rank_on_leaderboard(&mut leaderboard, &taqueria(10_000), 1);
+
+        assert_eq!(leaderboard.entries.len(), LEADERBOARD_SIZE);
+        assert!(leaderboard
+            .entries
+            .iter()
+            .all(|entry| entry.taqueria != taqueria(10_000)));
+        assert_eq!(
+            leaderboard.entries.last(),
+            Some(&SchemaLeaderboardStoredEntry {
+                taqueria: dropped,
+                weight: 2,
+            })
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn heavier_newcomer_pushes_out_the_lightest_entry() {
+        let mut leaderboard = full_board();
+
+        rank_on_leaderboard(&mut leaderboard, &taqueria(10_000), 1_050);
+
+        assert_eq!(leaderboard.entries.len(), LEADERBOARD_SIZE);
+        assert!(leaderboard
+            .entries
+            .iter()
+            .all(|entry| entry.taqueria != taqueria(0)));
+        assert!(leaderboard
+            .entries
+            .windows(2)
+            .all(|pair| pair[0].weight >= pair[1].weight));
+    }
+
+    #[wasm_bindgen_test]
+    fn ties_keep_the_earlier_holder_ahead() {
+        let mut leaderboard = SchemaLeaderboard::default();
+        rank_on_leaderboard(&mut leaderboard, &taqueria(1), 5);
+        rank_on_leaderboard(&mut leaderboard, &taqueria(2), 5);
+
+        let order: Vec<_> = leaderboard.entries.iter().map(|e| e.taqueria).collect();
+        assert_eq!(order, vec![taqueria(1), taqueria(2)]);
+    }
+
+    fn unbought(upgrade: &SchemaUpgradeCatalogueEntry) -> SchemaUserUpgradesEntry {
+        SchemaUserUpgradesEntry {
+            id: upgrade.id,
+            amount: 0,
+            next_price: upgrade.base_cost,
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn selling_what_was_bought_refunds_the_bps_of_each_unit_price() {
+        for bps in [0u16, 3_333, 5_000, 10_000] {
+            let upgrade = upgrade(3, 2);
+            let mut entry = unbought(&upgrade);
+
+            let mut prices = Vec::new();
+            let mut price = upgrade.base_cost;
+            for _ in 0..25 {
+                prices.push(price);
+                price = next_upgrade_price(&upgrade, price).unwrap();
+            }
+
+            let (bought, spent) = buy_upgrade_units(&upgrade, &mut entry, 25, u128::MAX).unwrap();
+            assert_eq!((bought, spent), (25, prices.iter().sum()));
+
+            let refunded: u128 = (0..25)
+                .map(|_| sell_upgrade_unit(&upgrade, &mut entry, bps).unwrap())
+                .sum();
+            let expected: u128 = prices
+                .iter()
+                .map(|price| price * u128::from(bps) / BPS_DENOMINATOR)
+                .sum();
+
+            assert_eq!(refunded, expected, "{bps} bps");
+            assert_eq!(entry, unbought(&upgrade));
+            assert!(sell_upgrade_unit(&upgrade, &mut entry, bps).is_err());
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn free_starter_unit_cannot_be_sold() {
+        let mut starter = upgrade(3, 2);
+        starter.id = STARTER_UPGRADE_ID;
+        let mut entry = SchemaUserUpgradesEntry {
+            amount: 1,
+            ..unbought(&starter)
+        };
+
+        assert!(sell_upgrade_unit(&starter, &mut entry, 10_000).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn buying_units_is_capped_per_call() {
+        let upgrade = upgrade(1, 1);
+
+        for quantity in [0, MAX_UPGRADES_PER_PURCHASE + 1] {
+            assert!(
+                buy_upgrade_units(&upgrade, &mut unbought(&upgrade), quantity, u128::MAX).is_err()
+            );
+        }
+
+        let mut entry = unbought(&upgrade);
+        let (bought, spent) =
+            buy_upgrade_units(&upgrade, &mut entry, MAX_UPGRADES_PER_PURCHASE, u128::MAX).unwrap();
+        assert_eq!(bought, u128::from(MAX_UPGRADES_PER_PURCHASE));
+        assert_eq!(spent, bought * upgrade.base_cost);
+        assert_eq!(entry.amount, bought);
+    }
+
+    #[wasm_bindgen_test]
+    fn buying_units_stops_at_the_first_unit_the_budget_cant_cover() {
+        let upgrade = upgrade(3, 2);
+        let second = next_upgrade_price(&upgrade, upgrade.base_cost).unwrap();
+        let budget = upgrade.base_cost + second + second - 1;
+
+        let mut entry = unbought(&upgrade);
+        let (bought, spent) = buy_upgrade_units(&upgrade, &mut entry, 10, budget).unwrap();
+
+        assert_eq!((bought, spent), (2, upgrade.base_cost + second));
+        assert_eq!(budget - spent, second - 1);
+    }
+
+    fn candidate(upgrade: &SchemaUpgradeCatalogueEntry) -> UpgradeCandidate<'_> {
+        UpgradeCandidate {
+            upgrade,
+            entry: unbought(upgrade),
+            emission: upgrade.weight,
+            purchase: SchemaCompoundPurchase {
+                upgrade: upgrade.id,
+                bought: 0,
+                spent: 0,
+            },
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn greedy_buying_is_capped_per_call() {
+        let flat = upgrade(1, 1);
+        let mut candidates = [candidate(&flat)];
+
+        let (added_weight, spent) = buy_upgrades_greedily(&mut candidates, u128::MAX).unwrap();
+
+        let cap = u128::from(MAX_UPGRADES_PER_PURCHASE);
+        assert_eq!(candidates[0].purchase.bought, cap);
+        assert_eq!(added_weight, cap * flat.weight);
+        assert_eq!(spent, cap * flat.base_cost);
+    }
+
+    #[wasm_bindgen_test]
+    fn greedy_buying_leaves_only_an_unspendable_remainder() {
+        let cheap = SchemaUpgradeCatalogueEntry {
+            id: 1,
+            base_cost: 10,
+            weight: 1,
+            ..upgrade(3, 2)
+        };
+        let dear = SchemaUpgradeCatalogueEntry {
+            id: 2,
+            base_cost: 45,
+            weight: 5,
+            ..upgrade(3, 2)
+        };
+        let budget = 200;
+        let mut candidates = [candidate(&cheap), candidate(&dear)];
+
+        let (added_weight, spent) = buy_upgrades_greedily(&mut candidates, budget).unwrap();
+        let remainder = budget - spent;
+
+        //Whatever is left cant buy another unit of anything, it goes back to the caller
+        assert!(candidates
+            .iter()
+            .all(|candidate| candidate.entry.next_price > remainder));
+        assert_eq!(
+            candidates.iter().map(|c| c.purchase.spent).sum::<u128>(),
+            spent
+        );
+        assert_eq!(
+            added_weight,
+            candidates
+                .iter()
+                .map(|c| c.purchase.bought * c.upgrade.weight)
+                .sum::<u128>()
+        );
+        assert!(remainder > 0);
     }
 }
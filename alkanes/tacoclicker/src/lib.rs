@@ -14,10 +14,11 @@ use alkanes_support::id::AlkaneId;
 use alkanes_support::parcel::AlkaneTransfer;
 use alkanes_support::response::CallResponse;
 use anyhow::{anyhow, ensure, Context, Result};
-use bitcoin::Transaction;
+use bitcoin::{Amount, ScriptBuf, Transaction, TxOut};
+use ordinals::{Artifact, Runestone};
+use protorune_support::protostone::Protostone;
 
 use borsh::BorshDeserialize;
-use consts::{FUNDING_ADDRESS, FUNDING_PRICE_SATS};
 use metashrew_support::compat::to_arraybuffer_layout;
 use metashrew_support::index_pointer::KeyValuePointer;
 use metashrew_support::utils::consensus_decode;
@@ -25,26 +26,65 @@ use std::sync::Arc;
 use token::MintableToken;
 
 use crate::consts::{
-    get_merkle_root_from_id, TORTILLA_AIRDROP_PREMINE, TORTILLA_CLAIM_WINDOW, TORTILLA_PER_BLOCK,
+    default_params, get_merkle_root_for_network, ADMIN_TOKEN_NAME, ADMIN_TOKEN_SYMBOL,
+    BPS_DENOMINATOR, MERKLE_DISTRIBUTOR_CLAIM_OPCODE, MERKLE_DISTRIBUTOR_SWEEP_OPCODE,
+    REFERRAL_POOL, TORTILLA_AIRDROP_PREMINE, TORTILLA_CLAIM_WINDOW,
+};
+use crate::game::consts::{
+    BET_COMMIT_DELAY, BET_HISTORY_SIZE, BET_REVEAL_WINDOW, DEFAULT_TAQUERIA_NAME,
+    DEFAULT_TAQUERIA_SYMBOL, MAX_REGISTRATIONS_PER_CALL, POC_RETARGET_INTERVAL,
+    POC_TARGET_PER_INTERVAL, SALSA_ROUND_BLOCKS, SHARE_SCALE,
+};
+use crate::game::emissions::{
+    emission_between, emission_rate_at, next_emission_change, ACC_PRECISION,
+};
+use crate::game::multipliers::{apply_multiplier, multiplier_from_seed, preview_bet, settle_bet};
+use crate::game::registration::{
+    registration_quote, validate_funding_address, validate_registration_policy,
 };
-use crate::game::consts::UPGRADES;
-use crate::game::multipliers::{apply_multiplier, multiplier_from_seed};
 use crate::game::schemas::{
-    SchemaBetOnBlockParameters, SchemaBetOnBlockResponse, SchemaBuyUpgradeParameters,
-    SchemaCompleteGlobalState, SchemaGetMultiplierFromHashParameters,
-    SchemaGetMultiplierFromHashResponse, SchemaGetTortillaPerBlockResponse,
-    SchemaGetUnclaimedTortillaResponse, SchemaGlobalEmissionState, SchemaGlobalSalsaState,
-    SchemaTaqueriaEmissionState, SchemaTaqueriaSpecificParameters, SchemaUpgradesEntry,
-    SchemaUpgradesView, SchemaUserUpgradesView, UpgradeKind,
+    SchemaBackfillTaqueriaListParameters, SchemaBetCommit, SchemaBetCommitView,
+    SchemaBetHistoryEntry, SchemaBetHistoryView, SchemaBetOnBlockParameters,
+    SchemaBetOnBlockResponse, SchemaBetStatsView, SchemaBuyUpgradeParameters,
+    SchemaBuyUpgradeResponse, SchemaClaimAllEntry, SchemaClaimAllResponse,
+    SchemaClaimTortillaAirdropParameters, SchemaClaimTortillaAirdropResponse,
+    SchemaCommitBetParameters, SchemaCommitBetResponse, SchemaCompleteGlobalState,
+    SchemaCompoundParameters, SchemaCompoundPurchase, SchemaCompoundResponse,
+    SchemaCompoundStrategy, SchemaEmissionDustView, SchemaEmissionProjection,
+    SchemaGetBetHistoryParameters, SchemaGetEmissionProjectionParameters,
+    SchemaGetMultiplierFromHashParameters, SchemaGetMultiplierFromHashResponse,
+    SchemaGetReferralsParameters, SchemaGetRegistrationQuoteParameters,
+    SchemaGetTortillaPerBlockResponse, SchemaGetUnclaimedTortillaResponse,
+    SchemaGlobalEmissionState, SchemaGlobalSalsaState, SchemaLeaderboardEntry,
+    SchemaLeaderboardView, SchemaListTaqueriasParameters, SchemaPocDifficultyView,
+    SchemaPreviewBetParameters, SchemaReferralsView, SchemaRegisterManyParameters,
+    SchemaRegisterParameters, SchemaRenameTaqueriaParameters, SchemaRevealBetResponse,
+    SchemaSalsaRoundParameters, SchemaSalsaRoundView, SchemaSellUpgradeParameters,
+    SchemaSellUpgradeResponse, SchemaTaqueriaCountResponse, SchemaTaqueriaDisplayName,
+    SchemaTaqueriaEmissionState, SchemaTaqueriaListEntry, SchemaTaqueriaListState,
+    SchemaTaqueriaListView, SchemaTaqueriaSelectionParameters, SchemaTaqueriaSpecificParameters,
+    SchemaUpgradesEntry, SchemaUpgradesView, SchemaUserUpgradesView,
+};
+use crate::game::utils::{
+    buy_upgrade_units, buy_upgrades_greedily, default_upgrade_catalogue, get_upgrade_by_id,
+    sell_upgrade_unit, upgrade_current_emission, validate_taqueria_name, validate_taqueria_symbol,
+    UpgradeCandidate,
 };
-use crate::game::utils::{get_upgrade_by_id, get_upgrade_entry_by_id, get_upgrade_entry_by_id_mut};
 use crate::schemas::{
+    SchemaAddUpgradeParameters, SchemaAdminChange, SchemaAdminLogEntry, SchemaAdminLogView,
     SchemaAlkaneId, SchemaAlkaneList, SchemaControlledMintInitializationParameters,
-    SchemaInitializeMerkleDistributorParameters, SchemaTacoClickerConsts,
-    SchemaTacoClickerInitializationParameters,
+    SchemaFundingRecipient, SchemaInitializeMerkleDistributorParameters, SchemaNetwork,
+    SchemaPaginationParameters, SchemaSetEmissionScheduleParameters,
+    SchemaSetFundingAddressParameters, SchemaSetFundingPriceParameters,
+    SchemaSetReferralBonusParameters, SchemaSetRegistrationPolicyParameters,
+    SchemaSetRenamePriceParameters, SchemaSetSalsaBlockRewardParameters,
+    SchemaSetSellRefundBpsParameters, SchemaSweepBeneficiary, SchemaTacoClickerConsts,
+    SchemaTacoClickerInitializationParameters, SchemaTransferAdminParameters,
 };
 use crate::utils::encoders::decode_from_ctx;
 use crate::utils::encoders::{address_from_txout, decode_from_vec, get_byte_array_from_inputs};
+use crate::utils::pagination::page_range;
+use crate::utils::signatures::spends_from_script;
 use bitcoin::hashes::Hash;
 
 #[derive(Default)]
@@ -56,7 +96,7 @@ impl MintableToken for Tortilla {}
 impl Tortilla {
     fn get_consts_value(&self) -> Result<SchemaTacoClickerConsts> {
         let bytes = (*self.get_consts_pointer().get()).clone();
-        decode_from_vec!(bytes, SchemaTacoClickerConsts)
+        SchemaTacoClickerConsts::decode(&bytes)
     }
 
     fn get_valid_taquerias_from_alkane_list(&self, list: &SchemaAlkaneList) -> Vec<SchemaAlkaneId> {
@@ -77,7 +117,44 @@ impl Tortilla {
         found_alkanes
     }
 
-    fn get_taqueria_from_call(&self, response: &mut CallResponse) -> Result<SchemaAlkaneId> {
+    //The selected taqueria if given, else the first one sent in. Every taqueria sent in is forwarded back either way
+    fn get_taqueria_from_call(
+        &self,
+        response: &mut CallResponse,
+        selected: Option<SchemaAlkaneId>,
+    ) -> Result<SchemaAlkaneId> {
+        let taquerias = self.get_taquerias_from_call(response)?;
+
+        if let Some(selected) = selected {
+            ensure!(
+                taquerias.contains(&selected),
+                "TORTILLA: taqueria {}:{} was not sent in with this call",
+                selected.block,
+                selected.tx
+            );
+            return Ok(selected);
+        }
+
+        let taqueria_alkane = taquerias.first().context(
+            "TORTILLA: Could not derive a taqueria from provided alkanes in transaction",
+        )?;
+        Ok(*taqueria_alkane)
+    }
+
+    //For actions without other parameters, calling with no inputs at all is the same as selecting None
+    fn decode_taqueria_selection(
+        &self,
+        ctx: &alkanes_support::context::Context,
+    ) -> Result<Option<SchemaAlkaneId>> {
+        if ctx.inputs.len() <= 1 {
+            return Ok(None);
+        }
+
+        Ok(decode_from_ctx!(ctx, SchemaTaqueriaSelectionParameters)?.taqueria)
+    }
+
+    //Every registered taqueria sent in, in transfer order and without repeats. May be empty
+    fn get_taquerias_from_call(&self, response: &mut CallResponse) -> Result<Vec<SchemaAlkaneId>> {
         let alkanes: Vec<SchemaAlkaneId> = response
             .alkanes
             .0
@@ -96,14 +173,42 @@ impl Tortilla {
 
         let alkane_list = SchemaAlkaneList { alkanes };
 
-        let taquerias = self.get_valid_taquerias_from_alkane_list(&alkane_list);
+        let mut taquerias = self.get_valid_taquerias_from_alkane_list(&alkane_list);
 
-        let taqueria_alkane = taquerias.get(0).context(
-            "TORTILLA: Could not derive a taqueria from provided alkanes in transaction",
-        )?;
-        Ok(*taqueria_alkane)
+        let mut seen = Vec::with_capacity(taquerias.len());
+        taquerias.retain(|taqueria| {
+            if seen.contains(taqueria) {
+                false
+            } else {
+                seen.push(*taqueria);
+                true
+            }
+        });
+
+        Ok(taquerias)
+    }
+
+    //Admin opcodes require the auth alkane to be sent in. It is forwarded back untouched with the rest of the response
+    fn assert_admin(&self, response: &CallResponse) -> Result<SchemaAlkaneId> {
+        let admin = self
+            .get_admin_value()?
+            .context("TORTILLA: no admin set yet, run BootstrapAdmin first")?;
+
+        ensure!(
+            !(admin.block == 0 && admin.tx == 0),
+            "TORTILLA: admin has been renounced"
+        );
+
+        ensure!(
+            response
+                .alkanes
+                .0
+                .iter()
+                .any(|transfer| transfer.id == admin.into() && transfer.value > 0),
+            "TORTILLA: caller did not provide the admin auth alkane"
+        );
 
-        // return something, or just Ok(alkane_list) if SchemaAlkaneId was a typo
+        Ok(admin)
     }
 
     //removes all transfers of "alkane" from response and returns a cumulative value of the amount collected (now controlled by the contract)
@@ -134,6 +239,62 @@ impl Tortilla {
 
         Ok(total)
     }
+
+    //Takes payment for `count` new taquerias at the current quote. If the policy has an alkane price and
+    //the caller sent that alkane it pays, the price stays with Tortilla as treasury and change is returned.
+    //Otherwise every recipient must be paid in sats
+    fn collect_registration_payment(&self, response: &mut CallResponse, count: u32) -> Result<()> {
+        let consts = self.get_consts_value()?;
+        let policy = self.get_params_value()?.registration;
+        let registered = self.get_taqueria_list_pointer().length();
+        let quote = registration_quote(&policy, registered, count)?;
+
+        if let Some(alkane_quote) = &quote.alkane {
+            let sent = self.collapse_transfers_for_alkane(alkane_quote.alkane, response)?;
+
+            if sent > 0 {
+                ensure!(
+                    sent >= alkane_quote.amount,
+                    "TORTILLA: for register, send {} of alkane {}:{} (got {})",
+                    alkane_quote.amount,
+                    alkane_quote.alkane.block,
+                    alkane_quote.alkane.tx,
+                    sent
+                );
+
+                if sent > alkane_quote.amount {
+                    response.alkanes.0.push(AlkaneTransfer {
+                        id: alkane_quote.alkane.into(),
+                        value: sent - alkane_quote.amount,
+                    });
+                }
+
+                return Ok(());
+            }
+        }
+
+        let tx = self
+            .get_serialized_transaction()
+            .context("TORTILLA: failed to get serialized parent transaction")?;
+
+        for recipient in &quote.recipients {
+            let paid: u64 = tx
+                .output
+                .iter()
+                .filter(|o| address_from_txout(o, consts.network.into()) == recipient.address)
+                .map(|o| o.value.to_sat())
+                .sum();
+
+            ensure!(
+                paid >= recipient.amount_sats,
+                "TORTILLA: for register, the parent tx must send {} sats to funding address {}",
+                recipient.amount_sats,
+                recipient.address
+            );
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(MessageDispatch)]
@@ -217,8 +378,129 @@ enum TortillaMessage {
     #[opcode(120)]
     GetMerkleDistributorId,
 
-    //#[opcode(118)]
-    //ClaimTortillaAirdrop,
+    #[opcode(121)]
+    GetUpgradeCatalogue,
+
+    #[opcode(122)]
+    GetAdmin,
+
+    #[opcode(123)]
+    GetParams,
+
+    #[opcode(124)]
+    GetAdminLog,
+
+    #[opcode(125)]
+    TransferAdmin,
+
+    #[opcode(126)]
+    RenounceAdmin,
+
+    #[opcode(127)]
+    SetEmissionSchedule,
+
+    #[opcode(128)]
+    SetSalsaBlockReward,
+
+    #[opcode(129)]
+    SetFundingPrice,
+
+    #[opcode(130)]
+    SetFundingAddress,
+
+    #[opcode(131)]
+    AddUpgrade,
+
+    #[opcode(132)]
+    GetEmissionProjection,
+
+    #[opcode(133)]
+    GetEmissionDust,
+
+    #[opcode(134)]
+    SellUpgrade,
+
+    #[opcode(135)]
+    SetSellRefundBps,
+
+    #[opcode(136)]
+    GetTaqueriaCount,
+
+    #[opcode(137)]
+    ListTaquerias,
+
+    #[opcode(138)]
+    GetLeaderboard,
+
+    #[opcode(139)]
+    GetSalsaRound,
+
+    #[opcode(140)]
+    ClaimSalsaPrize,
+
+    #[opcode(141)]
+    CommitBet,
+
+    #[opcode(142)]
+    RevealBet,
+
+    #[opcode(143)]
+    GetBetCommit,
+
+    #[opcode(144)]
+    GetBetHistory,
+
+    #[opcode(145)]
+    GetBetStats,
+
+    #[opcode(146)]
+    PreviewBet,
+
+    #[opcode(147)]
+    GetPocDifficulty,
+
+    #[opcode(148)]
+    ClaimAll,
+
+    #[opcode(149)]
+    Compound,
+
+    #[opcode(150)]
+    GetReferrals,
+
+    #[opcode(151)]
+    SetReferralBonus,
+
+    #[opcode(152)]
+    GetRegistrationQuote,
+
+    #[opcode(153)]
+    SetRegistrationPolicy,
+
+    #[opcode(154)]
+    RegisterMany,
+
+    #[opcode(155)]
+    RenameTaqueria,
+
+    #[opcode(156)]
+    GetTaqueriaDisplayName,
+
+    #[opcode(157)]
+    SetRenamePrice,
+
+    #[opcode(158)]
+    ClaimTortillaAirdrop,
+
+    #[opcode(159)]
+    SweepAirdrop,
+
+    #[opcode(160)]
+    BootstrapAdmin,
+
+    #[opcode(161)]
+    BackfillTaqueriaList,
+
     #[opcode(1000)]
     #[returns(Vec<u8>)]
     GetData,
@@ -231,6 +513,69 @@ impl Tortilla {
         Ok(tx)
     }
 
+    //Real output the protostone running this call points its alkanes at
+    fn get_pointer_output(&self, ctx: &alkanes_support::context::Context) -> Result<TxOut> {
+        let tx = self.get_serialized_transaction()?;
+
+        let runestone = match Runestone::decipher(&tx) {
+            Some(Artifact::Runestone(runestone)) => runestone,
+            _ => {
+                return Err(anyhow!(
+                    "TORTILLA: transaction does not contain a runestone"
+                ))
+            }
+        };
+
+        let protostones = Protostone::from_runestone(&runestone)
+            .map_err(|e| anyhow!("TORTILLA: failed to parse protostones: {e}"))?;
+
+        let index = ctx
+            .vout
+            .checked_sub(tx.output.len() as u32 + 1)
+            .context("TORTILLA: vout is not a protomessage")?;
+
+        let pointer = protostones
+            .get(index as usize)
+            .and_then(|protostone| protostone.pointer)
+            .context("TORTILLA: protostone has no pointer")?;
+
+        tx.output
+            .get(pointer as usize)
+            .cloned()
+            .context("TORTILLA: pointer is not a real output")
+    }
+
+    //Mints the 1/1 auth alkane into the response and makes it the admin
+    fn mint_admin_alkane(
+        &self,
+        response: &mut CallResponse,
+        controlled_mint_factory: SchemaAlkaneId,
+    ) -> Result<SchemaAlkaneId> {
+        let admin_alkane_id = self.clone_at_target(
+            response,
+            controlled_mint_factory.into(),
+            &SchemaControlledMintInitializationParameters {
+                token_name: ADMIN_TOKEN_NAME.to_string(),
+                token_symbol: ADMIN_TOKEN_SYMBOL.to_string(),
+                premine: 1u128,
+                cap: 1u128,
+            },
+        )?;
+
+        response.alkanes.0.push(AlkaneTransfer {
+            id: admin_alkane_id.into(),
+            value: 1u128,
+        });
+
+        self.set_admin(&admin_alkane_id)?;
+        self.append_admin_log(SchemaAdminChange::TransferAdmin {
+            previous: SchemaAlkaneId { block: 0, tx: 0 },
+            next: admin_alkane_id,
+        })?;
+
+        Ok(admin_alkane_id)
+    }
+
     pub fn blockhash(&self) -> Result<Vec<u8>> {
         Ok(self
             .block_header()
@@ -250,6 +595,22 @@ impl Tortilla {
 
         let init_params = decode_from_ctx!(context, SchemaTacoClickerInitializationParameters)?;
 
+        //Test deployments can bring their own airdrop tree, mainnet always uses the snapshot root
+        let airdrop_merkle_root = match init_params.airdrop_merkle_root {
+            Some(root) => {
+                ensure!(
+                    init_params.network != SchemaNetwork::Mainnet,
+                    "TORTILLA: mainnet cannot override the airdrop merkle root"
+                );
+                ensure!(
+                    root.len() == 32,
+                    "TORTILLA: airdrop merkle root must be 32 bytes"
+                );
+                root
+            }
+            None => get_merkle_root_for_network(init_params.network).to_vec(),
+        };
+
         let tortilla_alkane_id = self.clone_at_target(
             &mut response,
             init_params.controlled_mint_factory.into(),
@@ -267,21 +628,47 @@ impl Tortilla {
             value: TORTILLA_AIRDROP_PREMINE,
         });
 
+        //Tortilla sweeps the unclaimed airdrop itself, see SweepAirdrop
+        let myself = SchemaAlkaneId {
+            block: context
+                .myself
+                .block
+                .try_into()
+                .context("TORTILLA: contract id block overflows u32")?,
+            tx: context
+                .myself
+                .tx
+                .try_into()
+                .context("TORTILLA: contract id tx overflows u64")?,
+        };
+
         let merkle_distributor_alkane_id = self.clone_at_target(
             &mut response,
             init_params.merkle_distributor_factory.into(),
             &SchemaInitializeMerkleDistributorParameters {
-                merkle_root: get_merkle_root_from_id(init_params.merkle_root_id)?.to_vec(),
+                merkle_root: airdrop_merkle_root,
                 alkane_id: tortilla_alkane_id.into(),
                 amount: TORTILLA_AIRDROP_PREMINE,
                 block_end: self.height().saturating_add(TORTILLA_CLAIM_WINDOW).into(),
+                network: init_params.network,
+                sweep_beneficiary: SchemaSweepBeneficiary::Contract(myself),
             },
         )?;
 
+        //1/1 auth alkane, whoever holds it can call the admin opcodes
+        self.mint_admin_alkane(&mut response, init_params.controlled_mint_factory)?;
+
+        validate_funding_address(&init_params.funding_address, init_params.network)?;
+
+        let mut params = default_params(&init_params.funding_address);
+        params.emission_schedule.start_block = self.height().into();
+        self.set_params(&params)?;
+
         let consts = SchemaTacoClickerConsts {
             controlled_mint_factory: init_params.controlled_mint_factory,
             tortilla_alkane_id,
             merkle_distributor_alkane_id,
+            network: init_params.network,
         };
 
         let consumed_bytes = borsh::to_vec(&consts)?;
@@ -291,23 +678,27 @@ impl Tortilla {
             total_weight: 0u128,
             acc_reward_per_weight: 0u128,
             last_updated_block: self.height().into(),
+            acc_remainder: 0u128,
+            total_emitted: 0u128,
+            unallocated_emission: 0u128,
         };
 
-        self.get_global_emission_state_pointer().set(Arc::new(
-            borsh::to_vec(&initial_global_emissions_state)
-                .context("TORTILLA: failed to encode default emissions state")?,
-        ));
+        self.set_global_emission_state(&initial_global_emissions_state)
+            .context("TORTILLA: failed to encode default emissions state")?;
 
+        let salsa_round = self.current_salsa_round();
         let initial_salsa_state = SchemaGlobalSalsaState {
-            current_block: 0u128,
+            current_block: salsa_round * SALSA_ROUND_BLOCKS,
             best_hash: Vec::new(),
             best_hash_owner: Vec::new(),
+            round: salsa_round,
+            prize: params.salsa_block_reward,
         };
 
-        self.get_global_salsa_state_pointer().set(Arc::new(
-            borsh::to_vec(&initial_salsa_state)
-                .context("TORTILLA: failed to encode initial salsa state")?,
-        ));
+        self.set_global_salsa_state(&initial_salsa_state)
+            .context("TORTILLA: failed to encode initial salsa state")?;
+
+        self.set_upgrade_catalogue(&default_upgrade_catalogue())?;
 
         Ok(response)
     }
@@ -349,9 +740,9 @@ impl Tortilla {
 
         let params = decode_from_ctx!(context, SchemaTaqueriaSpecificParameters)?;
 
-        let consts = self.get_taqueria_emission_state_pointer(&params.taqueria)?;
-
-        let taqueria_emission_state_bytes = (*consts.get()).clone();
+        //Re-encoded so taquerias still stored in the legacy layout are returned in the current one
+        let taqueria_emission_state_bytes =
+            borsh::to_vec(&self.get_taqueria_emission_state_value(&params.taqueria)?)?;
 
         response.data = borsh::to_vec(&taqueria_emission_state_bytes)?;
 
@@ -362,15 +753,8 @@ impl Tortilla {
         let context = self.context()?;
         let mut response = CallResponse::forward(&context.incoming_alkanes);
 
-        let ptr_global_emission_state = self.get_global_emission_state_pointer();
-        let ptr_salsa_state = self.get_global_salsa_state_pointer();
-
-        let global_emission_state_bytes = (*ptr_global_emission_state.get()).clone();
-        let salsa_state_bytes = (*ptr_salsa_state.get()).clone();
-
-        let global_emission_state =
-            decode_from_vec!(global_emission_state_bytes, SchemaGlobalEmissionState)?;
-        let salsa_state = decode_from_vec!(salsa_state_bytes, SchemaGlobalSalsaState)?;
+        let global_emission_state = self.get_global_emission_state_value()?;
+        let salsa_state = self.get_global_salsa_state_value()?;
 
         let global_state_bytes = borsh::to_vec(&SchemaCompleteGlobalState {
             emission_state: global_emission_state,
@@ -389,41 +773,138 @@ impl Tortilla {
 
         let mut response = CallResponse::forward(&context.incoming_alkanes);
 
-        let tx = self
-            .get_serialized_transaction()
-            .context("TORTILLA: failed to get serialized parent transaction")?;
+        let register_params = if context.inputs.len() <= 1 {
+            SchemaRegisterParameters {
+                referrer: None,
+                name: None,
+                symbol: None,
+            }
+        } else {
+            decode_from_ctx!(context, SchemaRegisterParameters)?
+        };
 
-        let total_value_to_funding: u64 = tx
-            .output
-            .iter()
-            .filter(|o| address_from_txout(o) == FUNDING_ADDRESS)
-            .map(|o| o.value.to_sat())
-            .sum();
+        let next_alkane = self.register_taqueria(&mut response, register_params)?;
+
+        response.data = borsh::to_vec(&next_alkane)
+            .context("TORTILLA: failed to Borsh-serialize next_alkane")?;
+
+        Ok(response)
+    }
+
+    //Checks and takes payment for a single registration, shared by Register and ClaimTortillaAirdrop
+    fn register_taqueria(
+        &self,
+        response: &mut CallResponse,
+        register_params: SchemaRegisterParameters,
+    ) -> Result<SchemaAlkaneId> {
+        let referrer = register_params.referrer;
+
+        if let Some(name) = &register_params.name {
+            validate_taqueria_name(name)?;
+        }
+        if let Some(symbol) = &register_params.symbol {
+            validate_taqueria_symbol(symbol)?;
+        }
+
+        if let Some(referrer) = referrer {
+            ensure!(
+                self.get_taquerias_pointer(&referrer)?.get_value::<u8>() == 1u8,
+                "TORTILLA: referrer {}:{} is not a registered taqueria",
+                referrer.block,
+                referrer.tx
+            );
+
+            //A referrer sent in with the registration is held by the same outputs paying for it
+            ensure!(
+                !response
+                    .alkanes
+                    .0
+                    .iter()
+                    .any(|transfer| transfer.id == referrer.into()),
+                "TORTILLA: a taqueria cannot refer a registration it pays for"
+            );
+        }
+
+        let consts = self
+            .get_consts_value()
+            .context("TORTILLA: failed to fetch on-chain consts")?;
+
+        self.collect_registration_payment(response, 1)?;
+
+        let next_alkane = self.create_taqueria(
+            response,
+            &consts,
+            register_params.name,
+            register_params.symbol,
+        )?;
+
+        if let Some(referrer) = referrer {
+            self.record_referral(&next_alkane, &referrer)?;
+        }
+
+        Ok(next_alkane)
+    }
+
+    //Registers `count` taquerias for `count` times the price, walking the bonding curve as one batch
+    fn register_many(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaRegisterManyParameters)?;
 
         ensure!(
-            total_value_to_funding >= FUNDING_PRICE_SATS,
-            "TORTILLA: for register, the parent tx must send {FUNDING_PRICE_SATS} sats to funding address {FUNDING_ADDRESS}"
-    );
+            params.count > 0 && params.count <= MAX_REGISTRATIONS_PER_CALL,
+            "TORTILLA: count must be between 1 and {MAX_REGISTRATIONS_PER_CALL}"
+        );
 
         let consts = self
             .get_consts_value()
             .context("TORTILLA: failed to fetch on-chain consts")?;
 
+        self.collect_registration_payment(&mut response, params.count)?;
+
+        let alkanes = (0..params.count)
+            .map(|_| self.create_taqueria(&mut response, &consts, None, None))
+            .collect::<Result<Vec<_>>>()?;
+
+        response.data = borsh::to_vec(&SchemaAlkaneList { alkanes })?;
+
+        Ok(response)
+    }
+
+    //Clones a TAQUERIA, registers it and sends it out with the response. Payment and name checks are on the caller
+    fn create_taqueria(
+        &self,
+        response: &mut CallResponse,
+        consts: &SchemaTacoClickerConsts,
+        name: Option<String>,
+        symbol: Option<String>,
+    ) -> Result<SchemaAlkaneId> {
         let next_alkane = self.clone_at_target(
-            &mut response,
+            response,
             consts.controlled_mint_factory.into(),
             &SchemaControlledMintInitializationParameters {
-                token_name: "TAQUERIA".to_string(),
-                token_symbol: "TAQUERIA".to_string(),
+                token_name: name
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_TAQUERIA_NAME.to_string()),
+                token_symbol: symbol.unwrap_or_else(|| DEFAULT_TAQUERIA_SYMBOL.to_string()),
                 premine: 1u128,
                 cap: 1u128,
             },
         )?;
 
+        if let Some(name) = name {
+            self.get_taqueria_token_name_pointer(&next_alkane)?
+                .set(Arc::new(name.into_bytes()));
+        }
+
         self.get_taquerias_pointer(&next_alkane)
             .context("TORTILLA: could not get taqueria pointer")?
             .set_value(1u8);
 
+        self.get_taqueria_list_pointer().append(Arc::new(
+            borsh::to_vec(&next_alkane).context("TORTILLA: failed to encode taqueria id")?,
+        ));
+
         //transfer the alkane out of the initilzation contract to the main unallocated alkanes
         response.alkanes.0.push(AlkaneTransfer {
             id: AlkaneId {
@@ -435,172 +916,637 @@ impl Tortilla {
 
         self.create_taqueria_deps(&next_alkane)?;
 
-        response.data = borsh::to_vec(&next_alkane)
-            .context("TORTILLA: failed to Borsh-serialize next_alkane")?;
-
-        Ok(response)
+        Ok(next_alkane)
     }
 
-    fn buy_upgrade(&self) -> Result<CallResponse> {
+    //Paid in TORTILLA at the rename_price param, change is refunded. The payment stays in the contract
+    fn rename_taqueria(&self) -> Result<CallResponse> {
         let ctx = self.context()?;
         let mut response = CallResponse::forward(&ctx.incoming_alkanes);
-        let taqueria = self.get_taqueria_from_call(&mut response)?;
-        let params = decode_from_ctx!(ctx, SchemaBuyUpgradeParameters)?;
+        let params = decode_from_ctx!(ctx, SchemaRenameTaqueriaParameters)?;
+        let taqueria = self.get_taqueria_from_call(&mut response, params.taqueria)?;
         let consts = self.get_consts_value()?;
+
+        validate_taqueria_name(&params.name)?;
+
+        let rename_price = self.get_params_value()?.rename_price;
         let tortilla_recouped =
             self.collapse_transfers_for_alkane(consts.tortilla_alkane_id, &mut response)?;
 
-        let mut ptr_taqueria_upgrades = self.get_taqueria_upgrades_pointer(&taqueria)?;
-        let taqueria_upgrades_bytes = (*ptr_taqueria_upgrades.get()).clone();
-
-        let mut upgrades: SchemaUserUpgradesView =
-            decode_from_vec!(taqueria_upgrades_bytes, SchemaUserUpgradesView)?;
-        let entry = get_upgrade_entry_by_id_mut(&mut upgrades, params.upgrade.into())?;
         ensure!(
-            entry.next_price <= tortilla_recouped,
-            "TORTILLA: not enough tortilla for this upgrade"
+            tortilla_recouped >= rename_price,
+            "TORTILLA: renaming costs {rename_price} tortilla"
         );
 
-        self.update_global()?;
-
-        let mut ptr_global_emission_state = self.get_global_emission_state_pointer();
-        let mut ptr_taqueria_emission_state =
-            self.get_taqueria_emission_state_pointer(&taqueria)?;
-
-        let global_emission_state_bytes = (*ptr_global_emission_state.get()).clone();
-        let taqueria_emission_state_bytes = (*ptr_taqueria_emission_state.get()).clone();
-
-        let mut global: SchemaGlobalEmissionState =
-            decode_from_vec!(global_emission_state_bytes, SchemaGlobalEmissionState)?;
-        let mut taq_state: SchemaTaqueriaEmissionState =
-            decode_from_vec!(taqueria_emission_state_bytes, SchemaTaqueriaEmissionState)?;
-
-        let earned = taq_state
-            .taqueria_weight
-            .checked_mul(global.acc_reward_per_weight)
-            .context("TORTILLA: mul overflow at earned calculation")?
-            .checked_sub(taq_state.reward_debt)
-            .context("TORTILLA: sub underflow at earned calculation")?;
-        taq_state.pending = taq_state.pending.saturating_add(earned);
-
-        let add_w = get_upgrade_by_id(params.upgrade.into())?.weight;
-        taq_state.taqueria_weight = taq_state.taqueria_weight.saturating_add(add_w);
-        global.total_weight = global.total_weight.saturating_add(add_w);
-        taq_state.reward_debt = taq_state
-            .taqueria_weight
-            .checked_mul(global.acc_reward_per_weight)
-            .context("TORTILLA: mul overflow at debt calc")?;
-
-        //Refund user change that wasnt used to buy the upgrade
         response.alkanes.0.push(AlkaneTransfer {
             id: consts.tortilla_alkane_id.into(),
-            value: tortilla_recouped
-                .checked_sub(entry.next_price)
-                .context("TORTILLA: checked sub failed for refund")?,
+            value: tortilla_recouped - rename_price,
         });
 
-        entry.next_price = entry
-            .next_price
-            .checked_mul(3)
-            .context("TORTILLA: price overflow")?
-            .checked_div(2)
-            .unwrap(); // safe now
-        entry.amount = entry.amount.saturating_add(1u128);
-
-        ptr_taqueria_upgrades.set(Arc::new(borsh::to_vec(&upgrades)?));
-        ptr_taqueria_emission_state.set(Arc::new(borsh::to_vec(&taq_state)?));
-        ptr_global_emission_state.set(Arc::new(borsh::to_vec(&global)?));
+        self.get_taqueria_display_name_pointer(&taqueria)?
+            .set(Arc::new(params.name.into_bytes()));
 
         Ok(response)
     }
 
-    pub fn bet_on_block(&self) -> Result<CallResponse> {
+    fn get_taqueria_display_name(&self) -> Result<CallResponse> {
         let ctx = self.context()?;
-        let mut rsp = CallResponse::forward(&ctx.incoming_alkanes);
-        let params = decode_from_ctx!(ctx, SchemaBetOnBlockParameters)?;
-        let taqueria = self.get_taqueria_from_call(&mut rsp)?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaTaqueriaSpecificParameters)?;
 
-        self.proof_of_click(&taqueria, params.nonce_found_poc)
-            .context("TORTILLA: Proof‑of‑Click failed")?;
+        ensure!(
+            self.get_taquerias_pointer(&params.taqueria)?
+                .get_value::<u8>()
+                == 1u8,
+            "TORTILLA: {}:{} is not a registered taqueria",
+            params.taqueria.block,
+            params.taqueria.tx
+        );
 
-        let bhash_bytes = self.blockhash()?;
-        let multiplier = multiplier_from_seed(&bhash_bytes)?;
+        let display_name = (*self
+            .get_taqueria_display_name_pointer(&params.taqueria)?
+            .get())
+        .clone();
+        let token_name = (*self
+            .get_taqueria_token_name_pointer(&params.taqueria)?
+            .get())
+        .clone();
+
+        let view = if !display_name.is_empty() {
+            SchemaTaqueriaDisplayName {
+                name: String::from_utf8(display_name)
+                    .context("TORTILLA: stored display name is not utf8")?,
+                renamed: true,
+            }
+        } else if !token_name.is_empty() {
+            SchemaTaqueriaDisplayName {
+                name: String::from_utf8(token_name)
+                    .context("TORTILLA: stored token name is not utf8")?,
+                renamed: false,
+            }
+        } else {
+            SchemaTaqueriaDisplayName {
+                name: DEFAULT_TAQUERIA_NAME.to_string(),
+                renamed: false,
+            }
+        };
+
+        response.data = borsh::to_vec(&view)?;
 
-        let unclaimed = self.calc_unclaimed_tortilla(&taqueria)?;
+        Ok(response)
+    }
 
-        let mut ptr_taq = self.get_taqueria_emission_state_pointer(&taqueria)?;
+    fn buy_upgrade(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaBuyUpgradeParameters)?;
+        let taqueria = self.get_taqueria_from_call(&mut response, params.taqueria)?;
+        let consts = self.get_consts_value()?;
+        let tortilla_recouped =
+            self.collapse_transfers_for_alkane(consts.tortilla_alkane_id, &mut response)?;
 
-        let taqueria_emission_state_bytes = (*ptr_taq.get()).clone();
+        let catalogue = self.get_upgrade_catalogue_value()?;
+        let upgrade = get_upgrade_by_id(&catalogue, params.upgrade)?;
+        let mut entry = self.get_taqueria_upgrade(&taqueria, upgrade)?;
 
-        let mut taq: SchemaTaqueriaEmissionState =
-            decode_from_vec!(taqueria_emission_state_bytes, SchemaTaqueriaEmissionState)?;
+        let (bought, total_spent) =
+            buy_upgrade_units(upgrade, &mut entry, params.quantity, tortilla_recouped)?;
 
-        let (won_amt, lost_amt) = if multiplier > params.target_multiplier {
-            let new_amt = apply_multiplier(unclaimed, &bhash_bytes)?;
-            taq.pending = new_amt; // replace pending with boosted amount
-            (new_amt.saturating_sub(unclaimed), 0)
-        } else {
-            taq.pending = 0; // burn all unclaimed tortilla
-            (0, unclaimed)
-        };
+        ensure!(bought > 0, "TORTILLA: not enough tortilla for this upgrade");
 
-        // bring reward_debt up to date with latest acc index
-        let global: SchemaGlobalEmissionState = {
-            let b = (*self.get_global_emission_state_pointer().get()).clone();
-            decode_from_vec!(b, SchemaGlobalEmissionState)?
-        };
-        taq.reward_debt = taq
+        let added_weight = upgrade
+            .weight
+            .checked_mul(bought)
+            .context("TORTILLA: upgrade weight overflow")?;
+        let taqueria_weight = self
+            .get_taqueria_emission_state_value(&taqueria)?
             .taqueria_weight
-            .checked_mul(global.acc_reward_per_weight)
-            .context("TORTILLA: overflow updating reward debt")?;
+            .checked_add(added_weight)
+            .context("TORTILLA: taqueria weight overflow")?;
+        self.set_taqueria_weight(&taqueria, taqueria_weight)?;
 
-        self.update_global_salsa(&taqueria)?;
-
-        // persist taqueria state
-        ptr_taq.set(Arc::new(borsh::to_vec(&taq)?));
+        //Refund user change that wasnt used to buy the upgrade
+        response.alkanes.0.push(AlkaneTransfer {
+            id: consts.tortilla_alkane_id.into(),
+            value: tortilla_recouped
+                .checked_sub(total_spent)
+                .context("TORTILLA: checked sub failed for refund")?,
+        });
 
-        // ───────────────────────────── 6. build response
-        let resp_struct = SchemaBetOnBlockResponse {
-            won_amount: won_amt,
-            lost_amount: lost_amt,
-        };
-        rsp.data = borsh::to_vec(&resp_struct)?;
+        self.set_taqueria_upgrade(&taqueria, &entry)?;
 
-        Ok(rsp)
+        response.data = borsh::to_vec(&SchemaBuyUpgradeResponse {
+            bought,
+            total_spent,
+        })?;
+        Ok(response)
     }
 
-    pub fn claim_tortilla(&self) -> Result<CallResponse> {
+    //Spends unclaimed tortilla on upgrades in place, nothing is minted. At most MAX_UPGRADES_PER_PURCHASE units
+    //per call, whatever cant buy another unit stays pending
+    fn compound(&self) -> Result<CallResponse> {
         let ctx = self.context()?;
         let mut response = CallResponse::forward(&ctx.incoming_alkanes);
-        let taqueria = self.get_taqueria_from_call(&mut response)?;
+        let params = decode_from_ctx!(ctx, SchemaCompoundParameters)?;
+        let taqueria = self.get_taqueria_from_call(&mut response, params.taqueria)?;
 
-        let consts = self.get_consts_value()?;
+        //set_taqueria_weight settles the taqueria again, so pending is emptied before buying and
+        //whatever isnt spent is put back after
+        let budget = self.take_pending(&taqueria)?;
 
-        let ptr_global_emission_state = self.get_global_emission_state_pointer();
-        let mut ptr_taqueria_emission_state =
-            self.get_taqueria_emission_state_pointer(&taqueria.into())?;
+        let (purchases, total_spent) =
+            self.buy_upgrades_with_budget(&taqueria, &params.strategy, budget)?;
 
-        self.update_global()?;
+        ensure!(
+            total_spent > 0,
+            "TORTILLA: not enough unclaimed tortilla to compound"
+        );
 
-        let claim_amount = self.calc_unclaimed_tortilla(&taqueria.into())?;
-        ensure!(claim_amount > 0, "TORTILLA: nothing to claim");
+        let remaining_pending = budget - total_spent;
+        let mut taq = self.get_taqueria_emission_state_value(&taqueria)?;
+        taq.pending = taq
+            .pending
+            .checked_add(remaining_pending)
+            .context("TORTILLA: pending overflow")?;
+        self.set_taqueria_emission_state(&taqueria, &taq)?;
+
+        response.data = borsh::to_vec(&SchemaCompoundResponse {
+            purchases,
+            total_spent,
+            remaining_pending,
+        })?;
 
-        let global_emission_state_bytes = (*ptr_global_emission_state.get()).clone();
+        Ok(response)
+    }
 
-        let taqueria_emission_state_bytes = (*ptr_taqueria_emission_state.get()).clone();
+    //Greedily buys upgrades for the taqueria with up to `budget` tortilla, at most MAX_UPGRADES_PER_PURCHASE
+    //units. Only upgrades and weight are written, taking the budget from somewhere is up to the caller
+    fn buy_upgrades_with_budget(
+        &self,
+        taqueria: &SchemaAlkaneId,
+        strategy: &SchemaCompoundStrategy,
+        budget: u128,
+    ) -> Result<(Vec<SchemaCompoundPurchase>, u128)> {
+        let catalogue = self.get_upgrade_catalogue_value()?;
+        if let SchemaCompoundStrategy::Upgrade { upgrade } = strategy {
+            get_upgrade_by_id(&catalogue, *upgrade)?;
+        }
 
-        let global_emission_state: SchemaGlobalEmissionState =
-            decode_from_vec!(global_emission_state_bytes, SchemaGlobalEmissionState)?;
-        let mut taqueria_emission_state: SchemaTaqueriaEmissionState =
-            decode_from_vec!(taqueria_emission_state_bytes, SchemaTaqueriaEmissionState)?;
+        let tortilla_per_block = emission_rate_at(
+            &self.get_params_value()?.emission_schedule,
+            self.height().into(),
+        );
+        let total_weight = self.get_global_emission_state_value()?.total_weight;
+
+        let mut candidates = catalogue
+            .upgrades
+            .iter()
+            .filter(|upgrade| match strategy {
+                SchemaCompoundStrategy::Upgrade { upgrade: id } => upgrade.id == *id,
+                SchemaCompoundStrategy::BestEmissionPerCost => true,
+            })
+            .map(|upgrade| {
+                Ok(UpgradeCandidate {
+                    upgrade,
+                    entry: self.get_taqueria_upgrade(taqueria, upgrade)?,
+                    emission: upgrade_current_emission(
+                        upgrade.weight,
+                        tortilla_per_block,
+                        total_weight,
+                    ),
+                    purchase: SchemaCompoundPurchase {
+                        upgrade: upgrade.id,
+                        bought: 0,
+                        spent: 0,
+                    },
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let (added_weight, total_spent) = buy_upgrades_greedily(&mut candidates, budget)?;
+
+        if total_spent == 0 {
+            return Ok((Vec::new(), 0));
+        }
 
-        taqueria_emission_state.pending = 0;
-        taqueria_emission_state.reward_debt = taqueria_emission_state
+        let taqueria_weight = self
+            .get_taqueria_emission_state_value(taqueria)?
             .taqueria_weight
-            .checked_mul(global_emission_state.acc_reward_per_weight)
-            .context("TORTILLA: overflow updating debt")?;
+            .checked_add(added_weight)
+            .context("TORTILLA: taqueria weight overflow")?;
+        self.set_taqueria_weight(taqueria, taqueria_weight)?;
+
+        let mut purchases = Vec::new();
+        for candidate in candidates {
+            if candidate.purchase.bought > 0 {
+                self.set_taqueria_upgrade(taqueria, &candidate.entry)?;
+                purchases.push(candidate.purchase);
+            }
+        }
+
+        Ok((purchases, total_spent))
+    }
+
+    fn sell_upgrade(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaSellUpgradeParameters)?;
+        let taqueria = self.get_taqueria_from_call(&mut response, params.taqueria)?;
+        let consts = self.get_consts_value()?;
+
+        let catalogue = self.get_upgrade_catalogue_value()?;
+        let upgrade = get_upgrade_by_id(&catalogue, params.upgrade)?;
+        let mut entry = self.get_taqueria_upgrade(&taqueria, upgrade)?;
+
+        let refund_amount = sell_upgrade_unit(
+            upgrade,
+            &mut entry,
+            self.get_params_value()?.sell_refund_bps,
+        )?;
+
+        //set_taqueria_weight settles at the old weight before removing this unit's share
+        let taqueria_weight = self
+            .get_taqueria_emission_state_value(&taqueria)?
+            .taqueria_weight
+            .checked_sub(upgrade.weight)
+            .context("TORTILLA: taqueria weight underflow")?;
+        self.set_taqueria_weight(&taqueria, taqueria_weight)?;
+        self.set_taqueria_upgrade(&taqueria, &entry)?;
+
+        if refund_amount > 0 {
+            self.controlled_mint_contract_mint_new(
+                &response,
+                consts.tortilla_alkane_id.into(),
+                refund_amount,
+            )?;
+
+            response.alkanes.0.push(AlkaneTransfer {
+                id: consts.tortilla_alkane_id.into(),
+                value: refund_amount,
+            });
+        }
+
+        response.data = borsh::to_vec(&SchemaSellUpgradeResponse { refund_amount })?;
+        Ok(response)
+    }
+
+    pub fn bet_on_block(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut rsp = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaBetOnBlockParameters)?;
+        let taqueria = self.get_taqueria_from_call(&mut rsp, params.taqueria)?;
+
+        self.proof_of_click(&taqueria, params.nonce_found_poc)
+            .context("TORTILLA: Proof‑of‑Click failed")?;
+
+        let bhash_bytes = self.blockhash()?;
+        let multiplier = multiplier_from_seed(&bhash_bytes)?;
+
+        //Settling leaves reward_debt at the current accumulator, so only pending is touched below
+        let mut taq = self.settle_taqueria(&taqueria)?;
+
+        ensure!(params.stake_amount > 0, "TORTILLA: stake must be nonzero");
+        ensure!(
+            params.stake_amount <= taq.pending,
+            "TORTILLA: stake {} exceeds unclaimed tortilla {}",
+            params.stake_amount,
+            taq.pending
+        );
+
+        // the unstaked part stays pending whatever the outcome
+        let kept = taq.pending - params.stake_amount;
+
+        let (won_amt, lost_amt, payout) = if multiplier > params.target_multiplier {
+            let payout = apply_multiplier(params.stake_amount, &bhash_bytes)?;
+            (payout.saturating_sub(params.stake_amount), 0, payout)
+        } else {
+            (0, params.stake_amount, 0) // burn the stake
+        };
+
+        taq.pending = kept
+            .checked_add(payout)
+            .context("TORTILLA: pending overflow")?;
+
+        // final pending for this bet, salsa escrows its prize per round and never touches it
+        self.set_taqueria_emission_state(&taqueria, &taq)?;
+
+        self.record_bet(
+            &taqueria,
+            SchemaBetHistoryEntry {
+                block: self.height().into(),
+                target_multiplier: params.target_multiplier,
+                multiplier,
+                stake_amount: params.stake_amount,
+                won_amount: won_amt,
+                lost_amount: lost_amt,
+            },
+        )?;
+
+        self.update_global_salsa(&taqueria)?;
+
+        // ───────────────────────────── 6. build response
+        let resp_struct = SchemaBetOnBlockResponse {
+            won_amount: won_amt,
+            lost_amount: lost_amt,
+            stake_amount: params.stake_amount,
+            payout,
+            remaining_pending: taq.pending,
+        };
+        rsp.data = borsh::to_vec(&resp_struct)?;
+
+        Ok(rsp)
+    }
+
+    //Locks everything pending on a block BET_COMMIT_DELAY blocks ahead, so the hash cant be known when the bet is placed
+    fn commit_bet(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaCommitBetParameters)?;
+        let taqueria = self.get_taqueria_from_call(&mut response, params.taqueria)?;
+
+        self.proof_of_click(&taqueria, params.nonce_found_poc)
+            .context("TORTILLA: Proof‑of‑Click failed")?;
+
+        let now_block: u128 = self.height().into();
+
+        let mut taq = self.settle_taqueria(&taqueria)?;
+
+        //An expired commit is settled as forfeited before the new one replaces it
+        let forfeited = match self.get_bet_commit_value(&taqueria)? {
+            Some(existing) => {
+                ensure!(
+                    now_block > existing.target_block.saturating_add(BET_REVEAL_WINDOW),
+                    "TORTILLA: reveal the open bet (target block {}) first",
+                    existing.target_block
+                );
+                let outcome = self.settle_bet_commit(&existing, &mut taq)?;
+                self.record_bet_commit(&taqueria, &existing, &outcome)?;
+                Some(outcome)
+            }
+            None => None,
+        };
+
+        ensure!(taq.pending > 0, "TORTILLA: no unclaimed tortilla to bet");
+
+        let commit = SchemaBetCommit {
+            locked_amount: taq.pending,
+            target_multiplier: params.target_multiplier,
+            committed_block: now_block,
+            target_block: now_block
+                .checked_add(BET_COMMIT_DELAY)
+                .context("TORTILLA: target block overflow")?,
+        };
+
+        taq.pending = 0;
+        self.set_taqueria_emission_state(&taqueria, &taq)?;
+        self.set_bet_commit(&taqueria, &commit)?;
+
+        self.update_global_salsa(&taqueria)?;
+
+        response.data = borsh::to_vec(&SchemaCommitBetResponse { commit, forfeited })?;
+
+        Ok(response)
+    }
+
+    //Settles a commit against its target block hash, see settle_bet for expired and unrecorded bets
+    fn reveal_bet(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let selected = self.decode_taqueria_selection(&ctx)?;
+        let taqueria = self.get_taqueria_from_call(&mut response, selected)?;
+
+        let commit = self
+            .get_bet_commit_value(&taqueria)?
+            .context("TORTILLA: no open bet for this taqueria")?;
+
+        let now_block: u128 = self.height().into();
+        ensure!(
+            now_block >= commit.target_block,
+            "TORTILLA: bet can be revealed from block {}",
+            commit.target_block
+        );
+
+        //Also records the target hash when revealing at target or target + 1
+        let mut taq = self.settle_taqueria(&taqueria)?;
+
+        let resp_struct = self.settle_bet_commit(&commit, &mut taq)?;
+
+        self.set_taqueria_emission_state(&taqueria, &taq)?;
+        self.clear_bet_commit(&taqueria)?;
+
+        self.record_bet_commit(&taqueria, &commit, &resp_struct)?;
+
+        response.data = borsh::to_vec(&resp_struct)?;
+
+        Ok(response)
+    }
+
+    fn record_bet_commit(
+        &self,
+        taqueria: &SchemaAlkaneId,
+        commit: &SchemaBetCommit,
+        outcome: &SchemaRevealBetResponse,
+    ) -> Result<()> {
+        self.record_bet(
+            taqueria,
+            SchemaBetHistoryEntry {
+                block: commit.target_block,
+                target_multiplier: commit.target_multiplier,
+                multiplier: outcome.multiplier,
+                stake_amount: commit.locked_amount,
+                won_amount: outcome.won_amount,
+                lost_amount: outcome.lost_amount,
+            },
+        )
+    }
+
+    //Credits the outcome of a commit to taq.pending, see settle_bet for expired and unrecorded bets
+    fn settle_bet_commit(
+        &self,
+        commit: &SchemaBetCommit,
+        taq: &mut SchemaTaqueriaEmissionState,
+    ) -> Result<SchemaRevealBetResponse> {
+        let hash = self.get_recorded_block_hash(commit.target_block);
+        let outcome = settle_bet(commit, hash.as_deref(), self.height().into())?;
+
+        if outcome.lost_amount == 0 {
+            taq.pending = taq
+                .pending
+                .checked_add(commit.locked_amount)
+                .and_then(|pending| pending.checked_add(outcome.won_amount))
+                .context("TORTILLA: pending overflow")?;
+        }
+
+        Ok(outcome)
+    }
+
+    fn get_bet_commit(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaTaqueriaSpecificParameters)?;
+
+        response.data = borsh::to_vec(&SchemaBetCommitView {
+            commit: self.get_bet_commit_value(&params.taqueria)?,
+        })?;
+
+        Ok(response)
+    }
+
+    fn get_bet_history(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaGetBetHistoryParameters)?;
+
+        let bets = self.get_taqueria_bet_stats_value(&params.taqueria)?.bets;
+        let total = bets.min(BET_HISTORY_SIZE) as u32;
+
+        //Position 0 is the newest bet
+        let entries = page_range(total, params.offset, params.limit)
+            .map(|position| {
+                self.get_bet_history_entry(&params.taqueria, bets - 1 - u64::from(position))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        response.data = borsh::to_vec(&SchemaBetHistoryView { total, entries })?;
+
+        Ok(response)
+    }
+
+    fn get_bet_stats(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaTaqueriaSpecificParameters)?;
+
+        response.data = borsh::to_vec(&SchemaBetStatsView {
+            taqueria: self.get_taqueria_bet_stats_value(&params.taqueria)?,
+            global: self.get_global_bet_stats_value()?,
+        })?;
+
+        Ok(response)
+    }
+
+    fn get_salsa_round(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaSalsaRoundParameters)?;
+
+        let current_round = self.current_salsa_round();
+        ensure!(
+            params.round <= current_round,
+            "TORTILLA: salsa round {} has not started",
+            params.round
+        );
+
+        let salsa = self.get_global_salsa_state_value()?;
+        let round_block = params
+            .round
+            .checked_mul(SALSA_ROUND_BLOCKS)
+            .context("TORTILLA: salsa round overflow")?;
+
+        //The tracked round may have ended without being archived yet, it is reported as finished either way
+        let view = if params.round == salsa.round {
+            let leader = if salsa.best_hash_owner.is_empty() {
+                None
+            } else {
+                let owner_bytes = salsa.best_hash_owner.clone();
+                Some(decode_from_vec!(owner_bytes, SchemaAlkaneId)?)
+            };
+
+            SchemaSalsaRoundView {
+                round: salsa.round,
+                round_block: salsa.current_block,
+                best_hash: salsa.best_hash,
+                leader,
+                prize: salsa.prize,
+                finished: salsa.round < current_round,
+                claimed: false,
+            }
+        } else if let Some(archived) = self.get_salsa_round_value(params.round)? {
+            SchemaSalsaRoundView {
+                round: archived.round,
+                round_block: archived.round_block,
+                best_hash: archived.winning_hash,
+                leader: Some(archived.winner),
+                prize: archived.prize,
+                finished: true,
+                claimed: archived.claimed,
+            }
+        } else {
+            //Nobody played this round. If it is the current one the prize is what it will open with
+            let finished = params.round < current_round;
+
+            SchemaSalsaRoundView {
+                round: params.round,
+                round_block,
+                best_hash: Vec::new(),
+                leader: None,
+                prize: if finished {
+                    0u128
+                } else {
+                    self.get_params_value()?.salsa_block_reward
+                },
+                finished,
+                claimed: false,
+            }
+        };
+
+        response.data = borsh::to_vec(&view)?;
+        Ok(response)
+    }
+
+    fn claim_salsa_prize(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaSalsaRoundParameters)?;
+        let consts = self.get_consts_value()?;
+
+        //Archives the round if nobody has touched salsa since it ended
+        self.roll_salsa_round()?;
+
+        let mut round = self.get_salsa_round_value(params.round)?.context(
+            "TORTILLA: salsa round has no winner yet, it is either still running or nobody played",
+        )?;
+
+        ensure!(!round.claimed, "TORTILLA: salsa prize already claimed");
+        ensure!(
+            response
+                .alkanes
+                .0
+                .iter()
+                .any(|transfer| transfer.id == round.winner.into() && transfer.value > 0),
+            "TORTILLA: only the winning taqueria can claim this salsa prize"
+        );
+
+        round.claimed = true;
+        self.set_salsa_round(&round)?;
 
-        ptr_taqueria_emission_state.set(Arc::new(borsh::to_vec(&taqueria_emission_state)?));
+        if round.prize > 0 {
+            self.controlled_mint_contract_mint_new(
+                &response,
+                consts.tortilla_alkane_id.into(),
+                round.prize,
+            )?;
+
+            response.alkanes.0.push(AlkaneTransfer {
+                id: consts.tortilla_alkane_id.into(),
+                value: round.prize,
+            });
+        }
+
+        response.data = borsh::to_vec(&round)?;
+        Ok(response)
+    }
+
+    pub fn claim_tortilla(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let selected = self.decode_taqueria_selection(&ctx)?;
+        let taqueria = self.get_taqueria_from_call(&mut response, selected)?;
+
+        let consts = self.get_consts_value()?;
+
+        let claim_amount = self.take_pending(&taqueria)?;
+        ensure!(claim_amount > 0, "TORTILLA: nothing to claim");
 
         //Mint the tortilla
         self.controlled_mint_contract_mint_new(
@@ -617,129 +1563,249 @@ impl Tortilla {
         Ok(response)
     }
 
-    pub fn get_available_upgrades(&self) -> Result<CallResponse> {
+    //Same as claim_tortilla for every taqueria sent in, with a single mint for the total
+    pub fn claim_all(&self) -> Result<CallResponse> {
         let ctx = self.context()?;
         let mut response = CallResponse::forward(&ctx.incoming_alkanes);
-        let params = decode_from_ctx!(ctx, SchemaTaqueriaSpecificParameters)?;
-        let ptr_global_emission_state = self.get_global_emission_state_pointer();
+        let taquerias = self.get_taquerias_from_call(&mut response)?;
 
-        let global_emission_state_bytes = (*ptr_global_emission_state.get()).clone();
+        ensure!(
+            !taquerias.is_empty(),
+            "TORTILLA: Could not derive a taqueria from provided alkanes in transaction"
+        );
 
-        let global: SchemaGlobalEmissionState =
-            decode_from_vec!(global_emission_state_bytes, SchemaGlobalEmissionState)?;
+        let consts = self.get_consts_value()?;
 
-        let tortilla_per_block = TORTILLA_PER_BLOCK; // already ×10⁸
-        let total_weight = global.total_weight; // u128
-        let use_base_costs = params.taqueria.block == 0 && params.taqueria.tx == 0;
+        let mut total_claimed = 0u128;
+        let mut claims = Vec::with_capacity(taquerias.len());
 
-        let maybe_upgrades_view: Option<SchemaUserUpgradesView> = if !use_base_costs {
-            match self.get_taqueria_upgrades_pointer(&params.taqueria) {
-                Ok(ptr) => {
-                    let bytes = (*ptr.get()).clone();
-                    Some(decode_from_vec!(bytes, SchemaUserUpgradesView)?)
-                }
-                Err(_) => None, // not found; fall back to base costs
+        for taqueria in taquerias {
+            let claimed = self.take_pending(&taqueria)?;
+
+            total_claimed = total_claimed
+                .checked_add(claimed)
+                .context("TORTILLA: claim overflow")?;
+            claims.push(SchemaClaimAllEntry { taqueria, claimed });
+        }
+
+        ensure!(total_claimed > 0, "TORTILLA: nothing to claim");
+
+        self.controlled_mint_contract_mint_new(
+            &response,
+            consts.tortilla_alkane_id.into(),
+            total_claimed,
+        )?;
+
+        response.alkanes.0.push(AlkaneTransfer {
+            id: consts.tortilla_alkane_id.into(),
+            value: total_claimed,
+        });
+
+        response.data = borsh::to_vec(&SchemaClaimAllResponse {
+            total_claimed,
+            claims,
+        })?;
+
+        Ok(response)
+    }
+
+    //Claims the caller's airdrop through the merkle distributor in the same call. The distributor reads the
+    //merkle proof from this transaction's witness, so it is sent exactly as for a direct Claim, with the
+    //protomessage pointer at output 0. Unlike a direct Claim, edicts may send the taqueria or the
+    //registration alkane in. Registering happens before the claim so the registration price is never paid
+    //out of the airdrop. Registering or investing is only allowed when an input is signed by the output 0
+    //address, a plain claim must point at output 0
+    fn claim_tortilla_airdrop(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = if ctx.inputs.len() <= 1 {
+            SchemaClaimTortillaAirdropParameters {
+                register: None,
+                invest: None,
+                taqueria: None,
+                prevouts: Vec::new(),
             }
         } else {
-            None
+            decode_from_ctx!(ctx, SchemaClaimTortillaAirdropParameters)?
         };
 
-        fn make_entry(
-            id: UpgradeKind,
-            weight: u128,
-            base_cost: u128,
-            upgrades_view: Option<&SchemaUserUpgradesView>,
-            total_weight: u128,
-            tortilla_per_block: u128,
-        ) -> SchemaUpgradesEntry {
-            let next_cost = upgrades_view
-                .and_then(|v| get_upgrade_entry_by_id(v, id.into()).ok())
-                .map(|e| e.next_price)
-                .unwrap_or(base_cost);
-
-            let current_emission: u128 = if total_weight == 0 {
-                // Nobody has any weight yet; one unit of this upgrade captures the full emission.
-                TORTILLA_PER_BLOCK
-            } else {
-                weight
-                    .saturating_mul(tortilla_per_block) // guard overflow
-                    / total_weight // safe: divisor > 0
-            };
+        ensure!(
+            params.register.is_none() || params.taqueria.is_none(),
+            "TORTILLA: cannot select a taqueria while registering a new one"
+        );
+
+        //The distributor pays whoever output 0 is. Without the owner's signature the claim can only go
+        //there, otherwise anyone could pay a dust output to someone's address and spend their airdrop
+        let tx = self.get_serialized_transaction()?;
+        let leaf_script = tx
+            .output
+            .first()
+            .context("TORTILLA: vout #0 not present")?
+            .script_pubkey
+            .clone();
+
+        if params.register.is_some() || params.invest.is_some() {
+            let prevouts: Vec<TxOut> = params
+                .prevouts
+                .iter()
+                .map(|prevout| TxOut {
+                    value: Amount::from_sat(prevout.value),
+                    script_pubkey: ScriptBuf::from_bytes(prevout.script_pubkey.clone()),
+                })
+                .collect();
+
+            ensure!(
+                spends_from_script(&tx, &prevouts, &leaf_script)?,
+                "TORTILLA: registering or investing with the airdrop needs an input signed by the output 0 address"
+            );
+        } else {
+            ensure!(
+                self.get_pointer_output(&ctx)?.script_pubkey == leaf_script,
+                "TORTILLA: the airdrop claim must point at output 0"
+            );
+        }
 
-            SchemaUpgradesEntry {
-                cost: next_cost,
-                current_emission,
-                weight,
+        let consts = self.get_consts_value()?;
+
+        let taqueria = match params.register {
+            Some(register_params) => Some(self.register_taqueria(&mut response, register_params)?),
+            None if params.invest.is_some() => {
+                Some(self.get_taqueria_from_call(&mut response, params.taqueria)?)
+            }
+            None => None,
+        };
+
+        let claimed = self.call_distributor(
+            consts.merkle_distributor_alkane_id.into(),
+            MERKLE_DISTRIBUTOR_CLAIM_OPCODE,
+            consts.tortilla_alkane_id,
+        )?;
+
+        ensure!(claimed > 0, "TORTILLA: the airdrop claim paid out nothing");
+
+        let (purchases, total_spent) = match (&params.invest, &taqueria) {
+            (Some(strategy), Some(taqueria)) => {
+                self.buy_upgrades_with_budget(taqueria, strategy, claimed)?
             }
+            _ => (Vec::new(), 0),
+        };
+
+        //Upgrades keep the tortilla they were bought with, the rest of the airdrop goes to the caller
+        if claimed > total_spent {
+            response.alkanes.0.push(AlkaneTransfer {
+                id: consts.tortilla_alkane_id.into(),
+                value: claimed - total_spent,
+            });
         }
 
-        let view = SchemaUpgradesView {
-            taquero: make_entry(
-                UpgradeKind::Taquero,
-                UPGRADES.taquero.weight,
-                UPGRADES.taquero.base_cost,
-                maybe_upgrades_view.as_ref(),
-                total_weight,
-                tortilla_per_block,
-            ),
-            salsa_bar: make_entry(
-                UpgradeKind::SalsaBar,
-                UPGRADES.salsa_bar.weight,
-                UPGRADES.salsa_bar.base_cost,
-                maybe_upgrades_view.as_ref(),
-                total_weight,
-                tortilla_per_block,
-            ),
-            tortilla_tree: make_entry(
-                UpgradeKind::TortillaTree,
-                UPGRADES.tortilla_tree.weight,
-                UPGRADES.tortilla_tree.base_cost,
-                maybe_upgrades_view.as_ref(),
-                total_weight,
-                tortilla_per_block,
-            ),
-            tortilla_factory: make_entry(
-                UpgradeKind::TortillaFactory,
-                UPGRADES.tortilla_factory.weight,
-                UPGRADES.tortilla_factory.base_cost,
-                maybe_upgrades_view.as_ref(),
-                total_weight,
-                tortilla_per_block,
-            ),
-            taco_submarine: make_entry(
-                UpgradeKind::TacoBank,
-                UPGRADES.taco_submarine.weight,
-                UPGRADES.taco_submarine.base_cost,
-                maybe_upgrades_view.as_ref(),
-                total_weight,
-                tortilla_per_block,
-            ),
-            taco_pyramid: make_entry(
-                UpgradeKind::TacoPyramid,
-                UPGRADES.taco_pyramid.weight,
-                UPGRADES.taco_pyramid.base_cost,
-                maybe_upgrades_view.as_ref(),
-                total_weight,
-                tortilla_per_block,
-            ),
-            tortilla_spaceship: make_entry(
-                UpgradeKind::TortillaSpaceship,
-                UPGRADES.tortilla_spaceship.weight,
-                UPGRADES.tortilla_spaceship.base_cost,
-                maybe_upgrades_view.as_ref(),
-                total_weight,
-                tortilla_per_block,
-            ),
-            satoshi_tacomoto: make_entry(
-                UpgradeKind::SatoshiTacomoto,
-                UPGRADES.satoshi_tacomoto.weight,
-                UPGRADES.satoshi_tacomoto.base_cost,
-                maybe_upgrades_view.as_ref(),
-                total_weight,
-                tortilla_per_block,
-            ),
+        response.data = borsh::to_vec(&SchemaClaimTortillaAirdropResponse {
+            claimed,
+            taqueria,
+            purchases,
+            total_spent,
+        })?;
+
+        Ok(response)
+    }
+
+    //Admin only. After the claim window the unclaimed airdrop is swept from the distributor to this
+    //contract, which passes it on to the admin caller
+    fn sweep_airdrop(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        self.assert_admin(&response)?;
+
+        let consts = self.get_consts_value()?;
+        let swept = self.call_distributor(
+            consts.merkle_distributor_alkane_id.into(),
+            MERKLE_DISTRIBUTOR_SWEEP_OPCODE,
+            consts.tortilla_alkane_id,
+        )?;
+
+        response.alkanes.0.push(AlkaneTransfer {
+            id: consts.tortilla_alkane_id.into(),
+            value: swept,
+        });
+
+        response.data = borsh::to_vec(&swept)?;
+
+        Ok(response)
+    }
+
+    fn get_referrals(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaGetReferralsParameters)?;
+
+        let ptr_referred = self.get_referred_list_pointer(&params.taqueria)?;
+        let total_referred = ptr_referred.length();
+
+        let referred = page_range(total_referred, params.offset, params.limit)
+            .map(|index| {
+                let bytes = (*ptr_referred.select_index(index).get()).clone();
+                decode_from_vec!(bytes, SchemaAlkaneId)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        response.data = borsh::to_vec(&SchemaReferralsView {
+            referred_by: self.get_referral_value(&params.taqueria)?,
+            total_referred,
+            total_bonus: self
+                .get_referral_bonus_total_pointer(&params.taqueria)?
+                .get_value::<u128>(),
+            referred,
+        })?;
+
+        Ok(response)
+    }
+
+    pub fn get_available_upgrades(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaTaqueriaSpecificParameters)?;
+
+        let global = self.get_global_emission_state_value()?;
+
+        let tortilla_per_block = emission_rate_at(
+            &self.get_params_value()?.emission_schedule,
+            self.height().into(),
+        ); // already ×10⁸
+        let total_weight = global.total_weight; // u128
+        let use_base_costs = params.taqueria.block == 0 && params.taqueria.tx == 0;
+
+        let catalogue = self.get_upgrade_catalogue_value()?;
+
+        let maybe_upgrades_view: Option<SchemaUserUpgradesView> = if !use_base_costs {
+            Some(self.get_taqueria_upgrades(&params.taqueria, &catalogue)?)
+        } else {
+            None
         };
 
+        let upgrades = catalogue
+            .upgrades
+            .iter()
+            .map(|upgrade| {
+                let next_cost = maybe_upgrades_view
+                    .as_ref()
+                    .and_then(|v| v.upgrades.iter().find(|e| e.id == upgrade.id))
+                    .map(|e| e.next_price)
+                    .unwrap_or(upgrade.base_cost);
+
+                let current_emission =
+                    upgrade_current_emission(upgrade.weight, tortilla_per_block, total_weight);
+
+                SchemaUpgradesEntry {
+                    id: upgrade.id,
+                    name: upgrade.name.clone(),
+                    cost: next_cost,
+                    weight: upgrade.weight,
+                    current_emission,
+                }
+            })
+            .collect();
+
+        let view = SchemaUpgradesView { upgrades };
+
         response.data = borsh::to_vec(&view)?;
         Ok(response)
     }
@@ -749,26 +1815,20 @@ impl Tortilla {
         let mut response = CallResponse::forward(&ctx.incoming_alkanes);
         let params = decode_from_ctx!(ctx, SchemaTaqueriaSpecificParameters)?;
 
-        let global: SchemaGlobalEmissionState = {
-            let bytes = (*self.get_global_emission_state_pointer().get()).clone();
-            decode_from_vec!(bytes, SchemaGlobalEmissionState)?
-        };
-        let total_weight = global.total_weight;
-
-        let taq_state: SchemaTaqueriaEmissionState = {
-            let ptr = self
-                .get_taqueria_emission_state_pointer(&params.taqueria)
-                .context("TORTILLA: taqueria emission state not found")?;
-            let bytes = (*ptr.get()).clone();
-            decode_from_vec!(bytes, SchemaTaqueriaEmissionState)?
-        };
+        let total_weight = self.get_global_emission_state_value()?.total_weight;
+
+        let taq_state = self.get_taqueria_emission_state_value(&params.taqueria)?;
         let taq_weight = taq_state.taqueria_weight;
+        let tortilla_per_block = emission_rate_at(
+            &self.get_params_value()?.emission_schedule,
+            self.height().into(),
+        );
 
         let emission: u128 = if total_weight == 0 {
-            TORTILLA_PER_BLOCK
+            tortilla_per_block
         } else {
             taq_weight
-                .saturating_mul(TORTILLA_PER_BLOCK)   // overflow‑safe
+                .saturating_mul(tortilla_per_block)   // overflow‑safe
                 / total_weight // divisor > 0
         };
 
@@ -793,6 +1853,33 @@ impl Tortilla {
         Ok(response)
     }
 
+    fn get_poc_difficulty(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+
+        let state = self.get_poc_difficulty_value()?;
+
+        response.data = borsh::to_vec(&SchemaPocDifficultyView {
+            next_retarget_block: state
+                .window_start_block
+                .saturating_add(POC_RETARGET_INTERVAL),
+            target_per_interval: POC_TARGET_PER_INTERVAL,
+            state,
+        })?;
+
+        Ok(response)
+    }
+
+    fn preview_bet(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaPreviewBetParameters)?;
+
+        response.data = borsh::to_vec(&preview_bet(params.target_multiplier, params.amount)?)?;
+
+        Ok(response)
+    }
+
     fn get_multiplier_from_hash(&self) -> Result<CallResponse> {
         let ctx = self.context()?;
         let mut response = CallResponse::forward(&ctx.incoming_alkanes);
@@ -824,18 +1911,23 @@ impl Tortilla {
             "TORTILLA: taqueria upgrades not found"
         );
 
-        let ptr = self
-            .get_taqueria_upgrades_pointer(&params.taqueria)
-            .map_err(|_| anyhow!("TORTILLA: taqueria upgrades not found"))?;
-
-        let bytes = (*ptr.get()).clone();
-        let upgrades: SchemaUserUpgradesView = decode_from_vec!(bytes, SchemaUserUpgradesView)
+        let catalogue = self.get_upgrade_catalogue_value()?;
+        let upgrades = self
+            .get_taqueria_upgrades(&params.taqueria, &catalogue)
             .context("TORTILLA: failed to decode taqueria upgrades")?;
 
         response.data = borsh::to_vec(&upgrades)?;
         Ok(response)
     }
 
+    fn get_upgrade_catalogue(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+
+        response.data = borsh::to_vec(&self.get_upgrade_catalogue_value()?)?;
+        Ok(response)
+    }
+
     fn get_taqueria_from_alkane_list(&self) -> Result<CallResponse> {
         let context = self.context()?;
         let mut response = CallResponse::forward(&context.incoming_alkanes);
@@ -851,6 +1943,514 @@ impl Tortilla {
 
         Ok(response)
     }
+
+    fn get_admin(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+
+        let admin = self
+            .get_admin_value()?
+            .context("TORTILLA: no admin set yet, run BootstrapAdmin first")?;
+
+        response.data = borsh::to_vec(&admin)?;
+        Ok(response)
+    }
+
+    fn get_params(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+
+        response.data = borsh::to_vec(&self.get_params_value()?)?;
+        Ok(response)
+    }
+
+    fn get_emission_dust(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+
+        let global = self.get_projected_global_emission_state()?;
+
+        response.data = borsh::to_vec(&SchemaEmissionDustView {
+            total_emitted: global.total_emitted,
+            unallocated_emission: global.unallocated_emission,
+            acc_remainder: global.acc_remainder,
+            acc_precision: ACC_PRECISION,
+            total_weight: global.total_weight,
+        })?;
+        Ok(response)
+    }
+
+    fn get_emission_projection(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaGetEmissionProjectionParameters)?;
+
+        let schedule = self.get_params_value()?.emission_schedule;
+        let now_block: u128 = self.height().into();
+
+        let global = self.get_global_emission_state_value()?;
+
+        //Only emission after the last accrual is projected, everything before it is already in
+        //total_emitted or unallocated_emission
+        ensure!(
+            params.height >= global.last_updated_block,
+            "TORTILLA: cannot project supply before block {}",
+            global.last_updated_block
+        );
+
+        let projected_emission =
+            emission_between(&schedule, global.last_updated_block, params.height)?;
+
+        let scheduled_emission = TORTILLA_AIRDROP_PREMINE
+            .checked_add(global.total_emitted)
+            .and_then(|total| total.checked_add(global.unallocated_emission))
+            .and_then(|total| total.checked_add(projected_emission))
+            .context("TORTILLA: scheduled emission overflow")?;
+
+        let next_change_block = next_emission_change(&schedule, now_block);
+
+        let projection = SchemaEmissionProjection {
+            current_tortilla_per_block: emission_rate_at(&schedule, now_block),
+            next_tortilla_per_block: next_change_block
+                .map_or(0, |block| emission_rate_at(&schedule, block)),
+            next_change_block,
+            scheduled_emission,
+            unallocated_emission: global.unallocated_emission,
+            referral_bonus_paid: self.get_referral_bonus_paid_pointer().get_value::<u128>(),
+            referral_pool: REFERRAL_POOL,
+            schedule,
+        };
+
+        response.data = borsh::to_vec(&projection)?;
+        Ok(response)
+    }
+
+    fn get_taqueria_count(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+
+        response.data = borsh::to_vec(&SchemaTaqueriaCountResponse {
+            count: self.get_taqueria_list_pointer().length(),
+        })?;
+        Ok(response)
+    }
+
+    fn list_taquerias(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaListTaqueriasParameters)?;
+
+        let ptr_taqueria_list = self.get_taqueria_list_pointer();
+        let total = ptr_taqueria_list.length();
+
+        let taquerias = page_range(total, params.offset, params.limit)
+            .map(|index| {
+                let bytes = (*ptr_taqueria_list.select_index(index).get()).clone();
+                let taqueria = decode_from_vec!(bytes, SchemaAlkaneId)?;
+
+                let state = if params.include_state {
+                    Some(SchemaTaqueriaListState {
+                        taqueria_weight: self
+                            .get_taqueria_emission_state_value(&taqueria)?
+                            .taqueria_weight,
+                        unclaimed_tortilla: self.calc_unclaimed_tortilla(&taqueria)?,
+                    })
+                } else {
+                    None
+                };
+
+                Ok(SchemaTaqueriaListEntry { taqueria, state })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        response.data = borsh::to_vec(&SchemaTaqueriaListView { total, taquerias })?;
+        Ok(response)
+    }
+
+    fn get_leaderboard(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+
+        let total_weight = self.get_global_emission_state_value()?.total_weight;
+        let catalogue = self.get_upgrade_catalogue_value()?;
+
+        let entries = self
+            .get_leaderboard_value()?
+            .entries
+            .into_iter()
+            .zip(1u32..)
+            .map(|(entry, rank)| {
+                let share = entry
+                    .weight
+                    .checked_mul(SHARE_SCALE)
+                    .context("TORTILLA: overflow computing leaderboard share")?
+                    .checked_div(total_weight)
+                    .unwrap_or(0u128);
+
+                let upgrade_amounts = self
+                    .get_taqueria_upgrades(&entry.taqueria, &catalogue)?
+                    .upgrades
+                    .iter()
+                    .map(|upgrade| upgrade.amount)
+                    .collect();
+
+                Ok(SchemaLeaderboardEntry {
+                    rank,
+                    taqueria: entry.taqueria,
+                    weight: entry.weight,
+                    share,
+                    upgrade_amounts,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        response.data = borsh::to_vec(&SchemaLeaderboardView {
+            total_weight,
+            entries,
+        })?;
+        Ok(response)
+    }
+
+    fn get_admin_log(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+        let params = decode_from_ctx!(ctx, SchemaPaginationParameters)?;
+
+        let ptr_admin_log = self.get_admin_log_pointer();
+        let total = ptr_admin_log.length();
+
+        let entries = page_range(total, params.offset, params.limit)
+            .map(|index| {
+                let bytes = (*ptr_admin_log.select_index(index).get()).clone();
+                decode_from_vec!(bytes, SchemaAdminLogEntry)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        response.data = borsh::to_vec(&SchemaAdminLogView { total, entries })?;
+        Ok(response)
+    }
+
+    fn transfer_admin(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let response = CallResponse::forward(&ctx.incoming_alkanes);
+        let previous = self.assert_admin(&response)?;
+        let params = decode_from_ctx!(ctx, SchemaTransferAdminParameters)?;
+
+        ensure!(
+            !(params.new_admin.block == 0 && params.new_admin.tx == 0),
+            "TORTILLA: use RenounceAdmin to give up the admin role"
+        );
+
+        self.set_admin(&params.new_admin)?;
+        self.append_admin_log(SchemaAdminChange::TransferAdmin {
+            previous,
+            next: params.new_admin,
+        })?;
+
+        Ok(response)
+    }
+
+    //Deployments from before the admin role have nobody who can call the admin opcodes. Anyone can run
+    //this once, but the auth alkane only goes to the registration funding address, so the operator gets it
+    fn bootstrap_admin(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+
+        ensure!(
+            self.get_admin_value()?.is_none(),
+            "TORTILLA: admin is already set"
+        );
+
+        let consts = self.get_consts_value()?;
+        let params = self.get_params_value()?;
+        let funding_address = &params
+            .registration
+            .recipients
+            .first()
+            .context("TORTILLA: no funding address to send the admin alkane to")?
+            .address;
+
+        let output = self.get_pointer_output(&ctx)?;
+        ensure!(
+            &address_from_txout(&output, consts.network.into()) == funding_address,
+            "TORTILLA: the protostone pointer must be the funding address {}",
+            funding_address
+        );
+
+        self.mint_admin_alkane(&mut response, consts.controlled_mint_factory)?;
+
+        Ok(response)
+    }
+
+    fn renounce_admin(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let response = CallResponse::forward(&ctx.incoming_alkanes);
+        let previous = self.assert_admin(&response)?;
+
+        //Set to a valid id so we dont get decode failures on future asserts after renouncing
+        self.set_admin(&SchemaAlkaneId { block: 0, tx: 0 })?;
+        self.append_admin_log(SchemaAdminChange::RenounceAdmin { previous })?;
+
+        Ok(response)
+    }
+
+    fn set_emission_schedule(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let response = CallResponse::forward(&ctx.incoming_alkanes);
+        self.assert_admin(&response)?;
+        let params = decode_from_ctx!(ctx, SchemaSetEmissionScheduleParameters)?;
+
+        //Accrue everything up to this block on the old schedule before switching
+        self.update_global()?;
+
+        let mut current = self.get_params_value()?;
+        let previous = std::mem::replace(
+            &mut current.emission_schedule,
+            params.emission_schedule.clone(),
+        );
+
+        self.set_params(&current)?;
+        self.append_admin_log(SchemaAdminChange::EmissionSchedule {
+            previous,
+            next: params.emission_schedule,
+        })?;
+
+        Ok(response)
+    }
+
+    fn set_salsa_block_reward(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let response = CallResponse::forward(&ctx.incoming_alkanes);
+        self.assert_admin(&response)?;
+        let params = decode_from_ctx!(ctx, SchemaSetSalsaBlockRewardParameters)?;
+
+        let mut current = self.get_params_value()?;
+        let previous = current.salsa_block_reward;
+        current.salsa_block_reward = params.salsa_block_reward;
+
+        self.set_params(&current)?;
+        self.append_admin_log(SchemaAdminChange::SalsaBlockReward {
+            previous,
+            next: params.salsa_block_reward,
+        })?;
+
+        Ok(response)
+    }
+
+    fn set_sell_refund_bps(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let response = CallResponse::forward(&ctx.incoming_alkanes);
+        self.assert_admin(&response)?;
+        let params = decode_from_ctx!(ctx, SchemaSetSellRefundBpsParameters)?;
+
+        //Anything above 100% would let a buy and sell loop print tortilla
+        ensure!(
+            u128::from(params.sell_refund_bps) <= BPS_DENOMINATOR,
+            "TORTILLA: sell refund cannot exceed {BPS_DENOMINATOR} bps"
+        );
+
+        let mut current = self.get_params_value()?;
+        let previous = current.sell_refund_bps;
+        current.sell_refund_bps = params.sell_refund_bps;
+
+        self.set_params(&current)?;
+        self.append_admin_log(SchemaAdminChange::SellRefundBps {
+            previous,
+            next: params.sell_refund_bps,
+        })?;
+
+        Ok(response)
+    }
+
+    fn set_referral_bonus(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let response = CallResponse::forward(&ctx.incoming_alkanes);
+        self.assert_admin(&response)?;
+        let params = decode_from_ctx!(ctx, SchemaSetReferralBonusParameters)?;
+
+        let mut current = self.get_params_value()?;
+        validate_registration_policy(&current.registration, params.referral_bonus)?;
+
+        let previous = current.referral_bonus;
+        current.referral_bonus = params.referral_bonus;
+
+        self.set_params(&current)?;
+        self.append_admin_log(SchemaAdminChange::ReferralBonus {
+            previous,
+            next: params.referral_bonus,
+        })?;
+
+        Ok(response)
+    }
+
+    fn set_rename_price(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let response = CallResponse::forward(&ctx.incoming_alkanes);
+        self.assert_admin(&response)?;
+        let params = decode_from_ctx!(ctx, SchemaSetRenamePriceParameters)?;
+
+        let mut current = self.get_params_value()?;
+        let previous = current.rename_price;
+        current.rename_price = params.rename_price;
+
+        self.set_params(&current)?;
+        self.append_admin_log(SchemaAdminChange::RenamePrice {
+            previous,
+            next: params.rename_price,
+        })?;
+
+        Ok(response)
+    }
+
+    //The list only holds taquerias registered since it was added, so the count, the list views and the
+    //registration price all miss older ones until they are appended here
+    fn backfill_taqueria_list(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let response = CallResponse::forward(&ctx.incoming_alkanes);
+        self.assert_admin(&response)?;
+        let params = decode_from_ctx!(ctx, SchemaBackfillTaqueriaListParameters)?;
+
+        let ptr_taqueria_list = self.get_taqueria_list_pointer();
+        let mut listed = (0..ptr_taqueria_list.length())
+            .map(|index| {
+                let bytes = (*ptr_taqueria_list.select_index(index).get()).clone();
+                decode_from_vec!(bytes, SchemaAlkaneId)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for taqueria in &params.taquerias {
+            ensure!(
+                self.get_taquerias_pointer(taqueria)?.get_value::<u8>() == 1u8,
+                "TORTILLA: {}:{} is not a registered taqueria",
+                taqueria.block,
+                taqueria.tx
+            );
+            ensure!(
+                !listed.contains(taqueria),
+                "TORTILLA: taqueria {}:{} is already listed",
+                taqueria.block,
+                taqueria.tx
+            );
+
+            ptr_taqueria_list.append(Arc::new(
+                borsh::to_vec(taqueria).context("TORTILLA: failed to encode taqueria id")?,
+            ));
+            listed.push(*taqueria);
+        }
+
+        self.append_admin_log(SchemaAdminChange::TaqueriaListBackfill {
+            taquerias: params.taquerias,
+        })?;
+
+        Ok(response)
+    }
+
+    fn set_funding_price(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let response = CallResponse::forward(&ctx.incoming_alkanes);
+        self.assert_admin(&response)?;
+        let params = decode_from_ctx!(ctx, SchemaSetFundingPriceParameters)?;
+
+        let mut current = self.get_params_value()?;
+        let previous = current.registration.base_price_sats;
+        current.registration.base_price_sats = params.funding_price_sats;
+
+        self.set_params(&current)?;
+        self.append_admin_log(SchemaAdminChange::FundingPrice {
+            previous,
+            next: params.funding_price_sats,
+        })?;
+
+        Ok(response)
+    }
+
+    fn set_funding_address(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let response = CallResponse::forward(&ctx.incoming_alkanes);
+        self.assert_admin(&response)?;
+        let params = decode_from_ctx!(ctx, SchemaSetFundingAddressParameters)?;
+
+        validate_funding_address(&params.funding_address, self.get_consts_value()?.network)?;
+
+        //A single address replaces any split, SetRegistrationPolicy sets several recipients
+        let mut current = self.get_params_value()?;
+        let previous = current
+            .registration
+            .recipients
+            .iter()
+            .map(|recipient| recipient.address.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        current.registration.recipients = vec![SchemaFundingRecipient {
+            address: params.funding_address.clone(),
+            share_bps: BPS_DENOMINATOR as u16,
+        }];
+
+        self.set_params(&current)?;
+        self.append_admin_log(SchemaAdminChange::FundingAddress {
+            previous,
+            next: params.funding_address,
+        })?;
+
+        Ok(response)
+    }
+
+    fn set_registration_policy(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let response = CallResponse::forward(&ctx.incoming_alkanes);
+        self.assert_admin(&response)?;
+        let params = decode_from_ctx!(ctx, SchemaSetRegistrationPolicyParameters)?;
+
+        let mut current = self.get_params_value()?;
+        validate_registration_policy(&params.registration, current.referral_bonus)?;
+
+        let previous = std::mem::replace(&mut current.registration, params.registration.clone());
+
+        self.set_params(&current)?;
+        self.append_admin_log(SchemaAdminChange::RegistrationPolicy {
+            previous,
+            next: params.registration,
+        })?;
+
+        Ok(response)
+    }
+
+    fn get_registration_quote(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let mut response = CallResponse::forward(&ctx.incoming_alkanes);
+
+        let count = if ctx.inputs.len() <= 1 {
+            1
+        } else {
+            decode_from_ctx!(ctx, SchemaGetRegistrationQuoteParameters)?.count
+        };
+
+        let policy = self.get_params_value()?.registration;
+        let registered = self.get_taqueria_list_pointer().length();
+
+        response.data = borsh::to_vec(&registration_quote(&policy, registered, count)?)?;
+
+        Ok(response)
+    }
+
+    fn add_upgrade(&self) -> Result<CallResponse> {
+        let ctx = self.context()?;
+        let response = CallResponse::forward(&ctx.incoming_alkanes);
+        self.assert_admin(&response)?;
+        let params = decode_from_ctx!(ctx, SchemaAddUpgradeParameters)?;
+
+        let upgrade = self.add_upgrade_to_catalogue(
+            params.name,
+            params.base_cost,
+            params.weight,
+            params.price_growth_numerator,
+            params.price_growth_denominator,
+        )?;
+
+        self.append_admin_log(SchemaAdminChange::AddUpgrade { upgrade })?;
+
+        Ok(response)
+    }
 }
 
 impl AlkaneResponder for Tortilla {}
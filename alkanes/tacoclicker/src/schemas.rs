@@ -1,22 +1,38 @@
-use alkanes_support::id::AlkaneId;
+use anyhow::Result;
+use bitcoin::Network;
 use borsh::{BorshDeserialize, BorshSerialize};
-/*
-    Schema alkaneid uses u32's which have a max value of 4b. This fits well into the constrains of BTC (for block and sequence value)
-    Uses something like u128 in storage is very wasteful. Tx uses a u64 because the sequence pointer CAN eventually overflow 4b, but
-    for block which is literally just "2" on alkanes, there is no reason for this to be a u128. Infact i might make this a u8 lol.
-    nvm flex told me its not guaranteed to always be inside a u8 cuz of runes, but u32 is a safe assumption.
-*/
+
+use crate::game::schemas::{SchemaEmissionSchedule, SchemaUpgradeCatalogueEntry};
+use crate::utils::encoders::decode_from_vec;
+pub use tortilla_poc::schemas::SchemaAlkaneId;
+
+//Borsh discriminants line up with the old merkle_root_id (0 = regtest, 1 = mainnet)
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
-pub struct SchemaAlkaneId {
-    pub block: u32,
-    pub tx: u64,
+pub enum SchemaNetwork {
+    Regtest,
+    Mainnet,
+    Testnet,
+    Signet,
+}
+
+impl From<SchemaNetwork> for Network {
+    fn from(value: SchemaNetwork) -> Self {
+        match value {
+            SchemaNetwork::Regtest => Network::Regtest,
+            SchemaNetwork::Mainnet => Network::Bitcoin,
+            SchemaNetwork::Testnet => Network::Testnet,
+            SchemaNetwork::Signet => Network::Signet,
+        }
+    }
 }
 
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
 pub struct SchemaTacoClickerInitializationParameters {
     pub controlled_mint_factory: SchemaAlkaneId,
     pub merkle_distributor_factory: SchemaAlkaneId,
-    pub merkle_root_id: u8,
+    pub network: SchemaNetwork,  //also picks the airdrop merkle root
+    pub funding_address: String, //registration payments go here, must be an address on network
+    pub airdrop_merkle_root: Option<Vec<u8>>, //replaces the network root, refused on mainnet
 }
 
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
@@ -24,22 +40,97 @@ pub struct SchemaTacoClickerConsts {
     pub controlled_mint_factory: SchemaAlkaneId,
     pub tortilla_alkane_id: SchemaAlkaneId,
     pub merkle_distributor_alkane_id: SchemaAlkaneId,
+    pub network: SchemaNetwork,
 }
 
+//Layout from before network was stored, only read when decoding consts
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
-pub struct SchemaAlkaneList {
-    pub alkanes: Vec<SchemaAlkaneId>,
+pub struct SchemaLegacyTacoClickerConsts {
+    pub controlled_mint_factory: SchemaAlkaneId,
+    pub tortilla_alkane_id: SchemaAlkaneId,
+    pub merkle_distributor_alkane_id: SchemaAlkaneId,
 }
 
-impl From<SchemaAlkaneId> for AlkaneId {
-    fn from(value: SchemaAlkaneId) -> Self {
-        AlkaneId {
-            block: value.block.into(),
-            tx: value.tx.into(),
+impl SchemaTacoClickerConsts {
+    //Every deployment from before network was stored is a mainnet one
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        if let Ok(consts) = Self::try_from_slice(bytes) {
+            return Ok(consts);
         }
+
+        let legacy = decode_from_vec!(bytes, SchemaLegacyTacoClickerConsts)?;
+
+        Ok(SchemaTacoClickerConsts {
+            controlled_mint_factory: legacy.controlled_mint_factory,
+            tortilla_alkane_id: legacy.tortilla_alkane_id,
+            merkle_distributor_alkane_id: legacy.merkle_distributor_alkane_id,
+            network: SchemaNetwork::Mainnet,
+        })
     }
 }
 
+//Economic parameters the admin can change after deployment. Seeded from consts.rs at initialize
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SchemaTacoClickerParams {
+    pub emission_schedule: SchemaEmissionSchedule,
+    pub salsa_block_reward: u128,
+    pub sell_refund_bps: u16,
+    pub referral_bonus: u128,
+    pub rename_price: u128,
+    pub registration: SchemaRegistrationPolicy,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SchemaFundingRecipient {
+    pub address: String,
+    pub share_bps: u16, //all recipients add up to 10_000
+}
+
+//Optional price in an alkane (eg TORTILLA). It is not burned, it stays in Tortilla's balance as treasury.
+//No opcode spends that balance yet, so until one is added it is out of circulation
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SchemaAlkaneRegistrationPrice {
+    pub alkane: SchemaAlkaneId,
+    pub base_price: u128,
+    pub price_increment: u128,
+}
+
+//Registration is paid either in sats split across recipients, or in the alkane if the caller sends it in.
+//Both prices rise by their increment for every taqueria already registered
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SchemaRegistrationPolicy {
+    pub recipients: Vec<SchemaFundingRecipient>,
+    pub base_price_sats: u64,
+    pub price_increment_sats: u64,
+    pub alkane_price: Option<SchemaAlkaneRegistrationPrice>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaFundingRecipientQuote {
+    pub address: String,
+    pub amount_sats: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaAlkaneRegistrationQuote {
+    pub alkane: SchemaAlkaneId,
+    pub amount: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaRegistrationQuote {
+    pub registered: u32,
+    pub count: u32,
+    pub price_sats: u64,
+    pub recipients: Vec<SchemaFundingRecipientQuote>,
+    pub alkane: Option<SchemaAlkaneRegistrationQuote>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaAlkaneList {
+    pub alkanes: Vec<SchemaAlkaneId>,
+}
+
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
 pub struct SchemaControlledMintInitializationParameters {
     pub token_name: String,
@@ -53,4 +144,179 @@ pub struct SchemaInitializeMerkleDistributorParameters {
     pub alkane_id: SchemaAlkaneId,
     pub amount: u128,
     pub block_end: u128,
+    pub network: SchemaNetwork,
+    pub sweep_beneficiary: SchemaSweepBeneficiary,
+}
+
+//Mirrors the merkle distributor's sweep beneficiary
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
+pub enum SchemaSweepBeneficiary {
+    Contract(SchemaAlkaneId),
+    AuthAlkane(SchemaAlkaneId),
+    Burn,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaPaginationParameters {
+    pub offset: u32,
+    pub limit: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaTransferAdminParameters {
+    pub new_admin: SchemaAlkaneId,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaSetEmissionScheduleParameters {
+    pub emission_schedule: SchemaEmissionSchedule,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaSetSalsaBlockRewardParameters {
+    pub salsa_block_reward: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaSetSellRefundBpsParameters {
+    pub sell_refund_bps: u16,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaSetReferralBonusParameters {
+    pub referral_bonus: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaSetRenamePriceParameters {
+    pub rename_price: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaSetRegistrationPolicyParameters {
+    pub registration: SchemaRegistrationPolicy,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaSetFundingPriceParameters {
+    pub funding_price_sats: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaSetFundingAddressParameters {
+    pub funding_address: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaAddUpgradeParameters {
+    pub name: String,
+    pub base_cost: u128,
+    pub weight: u128,
+    pub price_growth_numerator: u128,
+    pub price_growth_denominator: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub enum SchemaAdminChange {
+    TransferAdmin {
+        previous: SchemaAlkaneId,
+        next: SchemaAlkaneId,
+    },
+    RenounceAdmin {
+        previous: SchemaAlkaneId,
+    },
+    EmissionSchedule {
+        previous: SchemaEmissionSchedule,
+        next: SchemaEmissionSchedule,
+    },
+    SalsaBlockReward {
+        previous: u128,
+        next: u128,
+    },
+    FundingPrice {
+        previous: u64,
+        next: u64,
+    },
+    FundingAddress {
+        previous: String,
+        next: String,
+    },
+    AddUpgrade {
+        upgrade: SchemaUpgradeCatalogueEntry,
+    },
+    SellRefundBps {
+        previous: u16,
+        next: u16,
+    },
+    ReferralBonus {
+        previous: u128,
+        next: u128,
+    },
+    RegistrationPolicy {
+        previous: SchemaRegistrationPolicy,
+        next: SchemaRegistrationPolicy,
+    },
+    RenamePrice {
+        previous: u128,
+        next: u128,
+    },
+    TaqueriaListBackfill {
+        taquerias: Vec<SchemaAlkaneId>,
+    },
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaAdminLogEntry {
+    pub block: u128,
+    pub change: SchemaAdminChange,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct SchemaAdminLogView {
+    pub total: u32,
+    pub entries: Vec<SchemaAdminLogEntry>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn id(tx: u64) -> SchemaAlkaneId {
+        SchemaAlkaneId { block: 2, tx }
+    }
+
+    #[wasm_bindgen_test]
+    fn legacy_consts_decode_as_mainnet() {
+        let bytes = borsh::to_vec(&SchemaLegacyTacoClickerConsts {
+            controlled_mint_factory: id(1),
+            tortilla_alkane_id: id(2),
+            merkle_distributor_alkane_id: id(3),
+        })
+        .unwrap();
+
+        assert!(SchemaTacoClickerConsts::try_from_slice(&bytes).is_err());
+        assert_eq!(
+            SchemaTacoClickerConsts::decode(&bytes).unwrap(),
+            SchemaTacoClickerConsts {
+                controlled_mint_factory: id(1),
+                tortilla_alkane_id: id(2),
+                merkle_distributor_alkane_id: id(3),
+                network: SchemaNetwork::Mainnet,
+            }
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn current_consts_keep_their_network() {
+        let consts = SchemaTacoClickerConsts {
+            controlled_mint_factory: id(1),
+            tortilla_alkane_id: id(2),
+            merkle_distributor_alkane_id: id(3),
+            network: SchemaNetwork::Regtest,
+        };
+
+        let bytes = borsh::to_vec(&consts).unwrap();
+        assert_eq!(SchemaTacoClickerConsts::decode(&bytes).unwrap(), consts);
+    }
 }
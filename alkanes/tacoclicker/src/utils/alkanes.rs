@@ -1,7 +1,7 @@
 use crate::{schemas::SchemaAlkaneId, utils::encoders::bytes_to_u128_words, Tortilla};
 use alkanes_runtime::runtime::AlkaneResponder;
 use alkanes_support::id::AlkaneId;
-use alkanes_support::{cellpack::Cellpack, response::CallResponse};
+use alkanes_support::{cellpack::Cellpack, parcel::AlkaneTransferParcel, response::CallResponse};
 use anyhow::{anyhow, Context, Result};
 use borsh::BorshSerialize;
 
@@ -52,6 +52,38 @@ impl Tortilla {
         Ok(next_alkane)
     }
 
+    //Calls a merkle distributor opcode (Claim or Sweep) and returns how much of `alkane` it paid
+    //out, which is now held by this contract. Sweep needs this contract as the sweep beneficiary
+    pub fn call_distributor(
+        &self,
+        target: AlkaneId,
+        opcode: u128,
+        alkane: SchemaAlkaneId,
+    ) -> Result<u128> {
+        let cellpack = Cellpack {
+            target,
+            inputs: vec![opcode],
+        };
+
+        let distributor_response = self
+            .call(&cellpack, &AlkaneTransferParcel::default(), self.fuel())
+            .map_err(|e| {
+                anyhow!(
+                    "TORTILLA: merkle distributor opcode {opcode} failed @ {},{} → {e}",
+                    target.block,
+                    target.tx
+                )
+            })?;
+
+        distributor_response
+            .alkanes
+            .0
+            .iter()
+            .filter(|transfer| transfer.id == alkane.into())
+            .try_fold(0u128, |total, transfer| total.checked_add(transfer.value))
+            .context("TORTILLA: overflow while summing merkle distributor transfers")
+    }
+
     pub fn controlled_mint_contract_mint_new(
         &self,
         response: &CallResponse,
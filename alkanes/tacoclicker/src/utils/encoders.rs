@@ -1,5 +1,4 @@
-use crate::consts::DEPLOYMENT_NETWORK;
-use bitcoin::{Address, TxOut};
+use bitcoin::{Address, Network, TxOut};
 
 pub fn u128_to_string(v: u128) -> String {
     String::from_utf8(
@@ -31,8 +30,8 @@ pub fn get_byte_array_from_inputs(inputs: &[u128]) -> Vec<u8> {
         .flat_map(|num| num.to_le_bytes()) // still LE
         .collect()
 }
-pub fn address_from_txout(output: &TxOut) -> String {
-    match Address::from_script(&output.script_pubkey, DEPLOYMENT_NETWORK) {
+pub fn address_from_txout(output: &TxOut, network: Network) -> String {
+    match Address::from_script(&output.script_pubkey, network) {
         Ok(address) => address.to_string(),
         Err(_) => String::new(),
     }
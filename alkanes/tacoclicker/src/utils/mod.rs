@@ -1,2 +1,4 @@
 pub mod alkanes;
 pub mod encoders;
+pub mod pagination;
+pub mod signatures;
@@ -0,0 +1,9 @@
+use crate::consts::MAX_PAGE_SIZE;
+use std::ops::Range;
+
+//Clamp an (offset, limit) request against a list of `total` entries
+pub fn page_range(total: u32, offset: u32, limit: u32) -> Range<u32> {
+    let start = offset.min(total);
+    let end = start.saturating_add(limit.min(MAX_PAGE_SIZE)).min(total);
+    start..end
+}
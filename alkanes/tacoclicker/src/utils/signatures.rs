@@ -0,0 +1,241 @@
+use anyhow::{ensure, Result};
+use bitcoin::hashes::Hash;
+use bitcoin::key::CompressedPublicKey;
+use bitcoin::secp256k1::{Message, Secp256k1, XOnlyPublicKey};
+use bitcoin::sighash::{EcdsaSighashType, Prevouts, SighashCache, TapSighashType};
+use bitcoin::{ecdsa, taproot, Script, ScriptBuf, Transaction, TxOut};
+
+/*
+    A contract only sees the transaction, not the outputs it spends, so the caller passes them in as prevouts.
+    A signature from script_pubkey's key only verifies against the sighash of this transaction with the real
+    prevouts, so lying about them just fails the check. Taproot key path and p2wpkh spends are recognised,
+    anything else (script paths, multisig, legacy) never counts as a spend from script_pubkey
+*/
+pub fn spends_from_script(
+    tx: &Transaction,
+    prevouts: &[TxOut],
+    script_pubkey: &Script,
+) -> Result<bool> {
+    ensure!(
+        prevouts.len() == tx.input.len(),
+        "TORTILLA: expected {} prevouts, got {}",
+        tx.input.len(),
+        prevouts.len()
+    );
+
+    let secp = Secp256k1::verification_only();
+    let mut cache = SighashCache::new(tx);
+
+    for (index, (input, prevout)) in tx.input.iter().zip(prevouts).enumerate() {
+        if prevout.script_pubkey.as_script() != script_pubkey {
+            continue;
+        }
+
+        let witness: Vec<&[u8]> = input.witness.iter().collect();
+
+        let signed = if script_pubkey.is_p2tr() {
+            //Key path only, a single signature and no annex
+            let [signature] = witness[..] else { continue };
+            let Ok(signature) = taproot::Signature::from_slice(signature) else {
+                continue;
+            };
+            if !matches!(
+                signature.sighash_type,
+                TapSighashType::Default | TapSighashType::All
+            ) {
+                continue;
+            }
+            let Ok(key) = XOnlyPublicKey::from_slice(&script_pubkey.as_bytes()[2..]) else {
+                continue;
+            };
+            let Ok(sighash) = cache.taproot_key_spend_signature_hash(
+                index,
+                &Prevouts::All(prevouts),
+                signature.sighash_type,
+            ) else {
+                continue;
+            };
+
+            secp.verify_schnorr(
+                &signature.signature,
+                &Message::from_digest(sighash.to_byte_array()),
+                &key,
+            )
+            .is_ok()
+        } else if script_pubkey.is_p2wpkh() {
+            let [signature, pubkey] = witness[..] else {
+                continue;
+            };
+            let Ok(pubkey) = CompressedPublicKey::from_slice(pubkey) else {
+                continue;
+            };
+            if ScriptBuf::new_p2wpkh(&pubkey.wpubkey_hash()).as_script() != script_pubkey {
+                continue;
+            }
+            let Ok(signature) = ecdsa::Signature::from_slice(signature) else {
+                continue;
+            };
+            if signature.sighash_type != EcdsaSighashType::All {
+                continue;
+            }
+            let Ok(sighash) = cache.p2wpkh_signature_hash(
+                index,
+                script_pubkey,
+                prevout.value,
+                signature.sighash_type,
+            ) else {
+                continue;
+            };
+
+            secp.verify_ecdsa(
+                &Message::from_digest(sighash.to_byte_array()),
+                &signature.signature,
+                &pubkey.0,
+            )
+            .is_ok()
+        } else {
+            false
+        };
+
+        if signed {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::key::{Keypair, TapTweak};
+    use bitcoin::secp256k1::SecretKey;
+    use bitcoin::{Amount, OutPoint, Sequence, Witness};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn keypair(byte: u8) -> Keypair {
+        Keypair::from_secret_key(
+            &Secp256k1::new(),
+            &SecretKey::from_slice(&[byte; 32]).unwrap(),
+        )
+    }
+
+    fn p2tr(keypair: &Keypair) -> ScriptBuf {
+        ScriptBuf::new_p2tr(&Secp256k1::new(), keypair.x_only_public_key().0, None)
+    }
+
+    fn p2wpkh(keypair: &Keypair) -> ScriptBuf {
+        ScriptBuf::new_p2wpkh(&CompressedPublicKey(keypair.public_key()).wpubkey_hash())
+    }
+
+    //Input 0 comes from someone else, input 1 from `script`, output 0 pays `script`
+    fn claim_tx(script: &ScriptBuf) -> (Transaction, Vec<TxOut>) {
+        let txin = |vout| bitcoin::TxIn {
+            previous_output: OutPoint {
+                txid: bitcoin::Txid::all_zeros(),
+                vout,
+            },
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::MAX,
+            witness: Witness::new(),
+        };
+        let tx = Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![txin(0), txin(1)],
+            output: vec![TxOut {
+                value: Amount::from_sat(546),
+                script_pubkey: script.clone(),
+            }],
+        };
+        let prevouts = vec![
+            TxOut {
+                value: Amount::from_sat(10_000),
+                script_pubkey: p2tr(&keypair(9)),
+            },
+            TxOut {
+                value: Amount::from_sat(20_000),
+                script_pubkey: script.clone(),
+            },
+        ];
+        (tx, prevouts)
+    }
+
+    fn sign_p2tr(tx: &mut Transaction, prevouts: &[TxOut], keypair: &Keypair) {
+        let secp = Secp256k1::new();
+        let sighash = SighashCache::new(&*tx)
+            .taproot_key_spend_signature_hash(1, &Prevouts::All(prevouts), TapSighashType::Default)
+            .unwrap();
+        let tweaked = keypair.tap_tweak(&secp, None).to_inner();
+        let signature = taproot::Signature {
+            signature: secp
+                .sign_schnorr_no_aux_rand(&Message::from_digest(sighash.to_byte_array()), &tweaked),
+            sighash_type: TapSighashType::Default,
+        };
+        tx.input[1].witness = Witness::p2tr_key_spend(&signature);
+    }
+
+    fn sign_p2wpkh(tx: &mut Transaction, prevouts: &[TxOut], keypair: &Keypair) {
+        let secp = Secp256k1::new();
+        let sighash = SighashCache::new(&*tx)
+            .p2wpkh_signature_hash(
+                1,
+                &prevouts[1].script_pubkey,
+                prevouts[1].value,
+                EcdsaSighashType::All,
+            )
+            .unwrap();
+        let signature = ecdsa::Signature {
+            signature: secp.sign_ecdsa(
+                &Message::from_digest(sighash.to_byte_array()),
+                &keypair.secret_key(),
+            ),
+            sighash_type: EcdsaSighashType::All,
+        };
+        tx.input[1].witness = Witness::p2wpkh(&signature, &keypair.public_key());
+    }
+
+    #[wasm_bindgen_test]
+    fn taproot_key_spend_from_the_script_counts() {
+        let owner = keypair(1);
+        let (mut tx, prevouts) = claim_tx(&p2tr(&owner));
+        sign_p2tr(&mut tx, &prevouts, &owner);
+
+        assert!(spends_from_script(&tx, &prevouts, &p2tr(&owner)).unwrap());
+    }
+
+    #[wasm_bindgen_test]
+    fn p2wpkh_spend_from_the_script_counts() {
+        let owner = keypair(1);
+        let (mut tx, prevouts) = claim_tx(&p2wpkh(&owner));
+        sign_p2wpkh(&mut tx, &prevouts, &owner);
+
+        assert!(spends_from_script(&tx, &prevouts, &p2wpkh(&owner)).unwrap());
+    }
+
+    #[wasm_bindgen_test]
+    fn someone_elses_signature_does_not_count() {
+        let owner = keypair(1);
+        let attacker = keypair(2);
+
+        //The attacker claims the prevout was the owner's, but can only sign with their own key
+        let (mut tx, prevouts) = claim_tx(&p2tr(&owner));
+        sign_p2tr(&mut tx, &prevouts, &attacker);
+        assert!(!spends_from_script(&tx, &prevouts, &p2tr(&owner)).unwrap());
+
+        //A real spend from the attacker's script, relabelled as the owner's prevout
+        let (mut tx, mut prevouts) = claim_tx(&p2tr(&attacker));
+        sign_p2tr(&mut tx, &prevouts, &attacker);
+        prevouts[1].script_pubkey = p2tr(&owner);
+        tx.output[0].script_pubkey = p2tr(&owner);
+        assert!(!spends_from_script(&tx, &prevouts, &p2tr(&owner)).unwrap());
+    }
+
+    #[wasm_bindgen_test]
+    fn unsigned_or_miscounted_prevouts_do_not_count() {
+        let owner = keypair(1);
+        let (tx, prevouts) = claim_tx(&p2tr(&owner));
+        assert!(!spends_from_script(&tx, &prevouts, &p2tr(&owner)).unwrap());
+        assert!(spends_from_script(&tx, &prevouts[..1], &p2tr(&owner)).is_err());
+    }
+}
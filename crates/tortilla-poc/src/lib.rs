@@ -0,0 +1,50 @@
+//Proof of click hashing. The contract checks clicks with these functions and the native miner searches
+//with them, so a nonce found off chain hashes exactly the same way on chain
+
+use borsh::{io, BorshSerialize};
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "miner")]
+pub mod miner;
+pub mod schemas;
+
+//Hasher fed with the borsh encoded taqueria id, which is the same for every nonce tried. Generic so the
+//contract and the miner both hash the contract's own SchemaAlkaneId
+pub fn poc_hasher<T: BorshSerialize>(taqueria: &T) -> io::Result<Sha256> {
+    let mut hasher = Sha256::new();
+    hasher.update(borsh::to_vec(taqueria)?);
+    Ok(hasher)
+}
+
+//sha256(borsh id ‖ 16 byte big endian nonce ‖ previous poc hash), the previous hash is empty on the first click
+pub fn poc_hash_from(prefix: &Sha256, nonce: u128, last_poc_hash: &[u8]) -> [u8; 32] {
+    let mut hasher = prefix.clone();
+    hasher.update(nonce.to_be_bytes());
+    hasher.update(last_poc_hash);
+    hasher.finalize().into()
+}
+
+pub fn poc_hash<T: BorshSerialize>(
+    taqueria: &T,
+    nonce: u128,
+    last_poc_hash: &[u8],
+) -> io::Result<[u8; 32]> {
+    Ok(poc_hash_from(&poc_hasher(taqueria)?, nonce, last_poc_hash))
+}
+
+pub fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0u32;
+
+    for byte in hash {
+        if *byte != 0 {
+            return bits + byte.leading_zeros();
+        }
+        bits += 8;
+    }
+
+    bits
+}
+
+pub fn meets_poc_difficulty(hash: &[u8], difficulty_bits: u8) -> bool {
+    leading_zero_bits(hash) >= u32::from(difficulty_bits)
+}
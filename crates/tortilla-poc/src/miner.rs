@@ -0,0 +1,153 @@
+use crate::{meets_poc_difficulty, poc_hash_from, poc_hasher};
+use borsh::{io, BorshSerialize};
+use sha2::Sha256;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PocSolution {
+    pub nonce: u128,
+    pub hash: [u8; 32],
+    pub attempts: u64,
+}
+
+//taqueria is hashed through its borsh encoding, pass the contract's SchemaAlkaneId
+pub struct PocMiner<T> {
+    pub taqueria: T,
+    pub last_poc_hash: Vec<u8>,
+    pub difficulty_bits: u8,
+    pub threads: usize,
+    pub start_nonce: u128,
+}
+
+struct PocSearch<'a> {
+    prefix: &'a Sha256,
+    last_poc_hash: &'a [u8],
+    difficulty_bits: u8,
+    step: u128,
+    found: &'a AtomicBool,
+    attempts: &'a AtomicU64,
+    solution: &'a Mutex<Option<(u128, [u8; 32])>>,
+}
+
+impl PocSearch<'_> {
+    fn run(&self, start_nonce: Option<u128>) {
+        let mut nonce = start_nonce;
+        let mut local_attempts = 0u64;
+
+        while let Some(current) = nonce {
+            //Checking the flag every nonce is cheap next to a sha256
+            if self.found.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let hash = poc_hash_from(self.prefix, current, self.last_poc_hash);
+            local_attempts += 1;
+
+            if meets_poc_difficulty(&hash, self.difficulty_bits) {
+                let mut best = self.solution.lock().unwrap_or_else(|e| e.into_inner());
+                //Keep the lowest nonce if two threads finish together
+                if best.is_none_or(|(nonce, _)| current < nonce) {
+                    *best = Some((current, hash));
+                }
+                self.found.store(true, Ordering::Relaxed);
+                break;
+            }
+
+            nonce = current.checked_add(self.step);
+        }
+
+        self.attempts.fetch_add(local_attempts, Ordering::Relaxed);
+    }
+}
+
+impl<T: BorshSerialize> PocMiner<T> {
+    //Thread i tries start_nonce + i, + i + threads, ... until any thread finds a hash with enough
+    //leading zero bits. A single thread searches inline, so targets without threads can mine too.
+    //Returns None only if every nonce up to u128::MAX was tried
+    pub fn mine(&self) -> io::Result<Option<PocSolution>> {
+        let threads = self.threads.max(1);
+        let prefix = poc_hasher(&self.taqueria)?;
+        let found = AtomicBool::new(false);
+        let attempts = AtomicU64::new(0);
+        let solution: Mutex<Option<(u128, [u8; 32])>> = Mutex::new(None);
+
+        let search = PocSearch {
+            prefix: &prefix,
+            last_poc_hash: &self.last_poc_hash,
+            difficulty_bits: self.difficulty_bits,
+            step: threads as u128,
+            found: &found,
+            attempts: &attempts,
+            solution: &solution,
+        };
+
+        if threads == 1 {
+            search.run(Some(self.start_nonce));
+        } else {
+            let start_nonce = self.start_nonce;
+
+            thread::scope(|scope| {
+                for offset in 0..threads {
+                    let search = &search;
+                    scope.spawn(move || search.run(start_nonce.checked_add(offset as u128)));
+                }
+            });
+        }
+
+        let best = solution.into_inner().unwrap_or_else(|e| e.into_inner());
+
+        Ok(best.map(|(nonce, hash)| PocSolution {
+            nonce,
+            hash,
+            attempts: attempts.into_inner(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poc_hash;
+    use crate::schemas::SchemaAlkaneId;
+
+    fn miner(threads: usize) -> PocMiner<SchemaAlkaneId> {
+        PocMiner {
+            taqueria: SchemaAlkaneId { block: 2, tx: 7 },
+            last_poc_hash: vec![0xab; 32],
+            difficulty_bits: 10,
+            threads,
+            start_nonce: 1_000,
+        }
+    }
+
+    #[test]
+    fn mined_nonce_verifies_with_proof_of_click_hashing() {
+        for threads in [1, 4] {
+            let miner = miner(threads);
+            let solution = miner.mine().unwrap().unwrap();
+
+            //The contract recomputes the hash from the taqueria id, it never sees the miner's prefix
+            let hash = poc_hash(&miner.taqueria, solution.nonce, &miner.last_poc_hash).unwrap();
+            assert_eq!(hash, solution.hash);
+            assert!(meets_poc_difficulty(&hash, miner.difficulty_bits));
+            assert!(solution.nonce >= miner.start_nonce);
+        }
+    }
+
+    #[test]
+    fn single_thread_returns_the_first_nonce_that_meets_the_difficulty() {
+        let miner = miner(1);
+        let solution = miner.mine().unwrap().unwrap();
+
+        assert!((miner.start_nonce..solution.nonce).all(|nonce| {
+            let hash = poc_hash(&miner.taqueria, nonce, &miner.last_poc_hash).unwrap();
+            !meets_poc_difficulty(&hash, miner.difficulty_bits)
+        }));
+        assert_eq!(
+            solution.attempts as u128,
+            solution.nonce - miner.start_nonce + 1
+        );
+    }
+}
@@ -1,5 +1,121 @@
 // src/main.rs
+// Native proof-of-click miner. Finds a nonce_found_poc for a taqueria and prints the borsh encoded
+// BetOnBlock parameters, ready to be appended to the cellpack inputs
 
-fn main() {
-    println!("Hello, world!");
-}
\ No newline at end of file
+use anyhow::{anyhow, bail, Context, Result};
+use std::time::Instant;
+use tortilla_poc::miner::PocMiner;
+use tortilla_poc::schemas::{SchemaAlkaneId, SchemaBetOnBlockParameters};
+
+const USAGE: &str =
+    "usage: tacoclicker-mono <taqueria block:tx> <last_poc_hash hex, empty on the first click>
+    [--difficulty <bits, default 8>] [--threads <n, default all cores>] [--start-nonce <n>]
+    [--target-multiplier <scaled 1e8>] [--stake <amount>]";
+
+struct Args {
+    taqueria: SchemaAlkaneId,
+    last_poc_hash: Vec<u8>,
+    difficulty_bits: u8,
+    threads: usize,
+    start_nonce: u128,
+    target_multiplier: u128,
+    stake_amount: Option<u128>,
+}
+
+fn parse_taqueria(value: &str) -> Result<SchemaAlkaneId> {
+    let (block, tx) = value
+        .split_once(':')
+        .ok_or_else(|| anyhow!("taqueria must be block:tx"))?;
+
+    Ok(SchemaAlkaneId {
+        block: block.parse().context("invalid taqueria block")?,
+        tx: tx.parse().context("invalid taqueria tx")?,
+    })
+}
+
+fn parse_args() -> Result<Args> {
+    let mut positional = Vec::new();
+    let mut flags = std::env::args().skip(1);
+
+    let mut difficulty_bits = 8u8;
+    let mut threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut start_nonce = 0u128;
+    let mut target_multiplier = 0u128;
+    let mut stake_amount = None;
+
+    while let Some(arg) = flags.next() {
+        let mut value = || flags.next().ok_or_else(|| anyhow!("{} needs a value", arg));
+
+        match arg.as_str() {
+            "--difficulty" => difficulty_bits = value()?.parse().context("invalid --difficulty")?,
+            "--threads" => threads = value()?.parse().context("invalid --threads")?,
+            "--start-nonce" => start_nonce = value()?.parse().context("invalid --start-nonce")?,
+            "--target-multiplier" => {
+                target_multiplier = value()?.parse().context("invalid --target-multiplier")?
+            }
+            "--stake" => stake_amount = Some(value()?.parse().context("invalid --stake")?),
+            "-h" | "--help" => bail!(USAGE),
+            _ => positional.push(arg),
+        }
+    }
+
+    let [taqueria, last_poc_hash] =
+        <[String; 2]>::try_from(positional).map_err(|_| anyhow!(USAGE))?;
+
+    Ok(Args {
+        taqueria: parse_taqueria(&taqueria)?,
+        last_poc_hash: hex::decode(last_poc_hash.trim_start_matches("0x"))
+            .context("invalid last_poc_hash")?,
+        difficulty_bits,
+        threads,
+        start_nonce,
+        target_multiplier,
+        stake_amount,
+    })
+}
+
+fn main() -> Result<()> {
+    let args = parse_args()?;
+
+    let miner = PocMiner {
+        taqueria: args.taqueria,
+        last_poc_hash: args.last_poc_hash,
+        difficulty_bits: args.difficulty_bits,
+        threads: args.threads,
+        start_nonce: args.start_nonce,
+    };
+
+    let started = Instant::now();
+    let solution = miner
+        .mine()?
+        .ok_or_else(|| anyhow!("nonce space exhausted without a solution"))?;
+    let elapsed = started.elapsed().as_secs_f64();
+
+    println!("nonce_found_poc: {}", solution.nonce);
+    println!("poc_hash: {}", hex::encode(solution.hash));
+    println!(
+        "attempts: {} ({:.0} H/s on {} threads)",
+        solution.attempts,
+        solution.attempts as f64 / elapsed.max(f64::EPSILON),
+        args.threads.max(1)
+    );
+
+    match args.stake_amount {
+        Some(stake_amount) => {
+            let params = SchemaBetOnBlockParameters {
+                nonce_found_poc: solution.nonce,
+                target_multiplier: args.target_multiplier,
+                stake_amount,
+                //The nonce only works for this taqueria, so select it explicitly
+                taqueria: Some(args.taqueria),
+            };
+            println!(
+                "bet_on_block_params: {}",
+                hex::encode(borsh::to_vec(&params)?)
+            );
+        }
+        None => println!("pass --stake to also encode BetOnBlock parameters"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,235 @@
+// tacoclicker-rs/src/tests/merkle_distributor.rs
+// E2E tests for the merkle distributor claim flow
+// Deploys a controlled mint, funds a distributor with a one leaf tree and claims against it
+
+use crate::tests::helpers::{
+    clear, create_multiple_cellpack_with_witness_and_txins_edicts, get_sheet_for_outpoint,
+};
+use crate::tests::std::{controlled_mint_build, merkle_distributor_build};
+use alkanes::indexer::index_block;
+use alkanes_support::cellpack::Cellpack;
+use alkanes_support::envelope::RawEnvelope;
+use alkanes_support::id::AlkaneId;
+use anyhow::Result;
+use bitcoin::{OutPoint, ScriptBuf, Sequence, TxIn, Txid, Witness};
+use borsh::BorshSerialize;
+use protorune::test_helpers::{create_block_with_coinbase_tx, ADDRESS1};
+use protorune_support::balance_sheet::ProtoruneRuneId;
+use protorune_support::protostone::ProtostoneEdict;
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+use wasm_bindgen_test::*;
+
+const BLOCK_HEIGHT: u32 = 840_000;
+const AIRDROP_AMOUNT: u128 = 1_000;
+pub const LEAF_AMOUNT: u128 = 600;
+
+//Borsh layouts the contracts decode, as a client would encode them
+#[derive(BorshSerialize)]
+pub struct SchemaAlkaneId {
+    pub block: u32,
+    pub tx: u64,
+}
+
+#[derive(BorshSerialize)]
+pub struct SchemaControlledMintInitializationParameters {
+    pub token_name: String,
+    pub token_symbol: String,
+    pub premine: u128,
+    pub cap: u128,
+}
+
+#[derive(BorshSerialize)]
+pub enum SchemaNetwork {
+    Regtest,
+}
+
+#[derive(BorshSerialize)]
+pub enum SchemaSweepBeneficiary {
+    #[allow(dead_code)]
+    Contract(SchemaAlkaneId),
+    #[allow(dead_code)]
+    AuthAlkane(SchemaAlkaneId),
+    Burn,
+}
+
+#[derive(BorshSerialize)]
+pub struct SchemaInitializeMerkleDistributorParameters {
+    pub merkle_root: Vec<u8>,
+    pub alkane_id: SchemaAlkaneId,
+    pub amount: u128,
+    pub block_end: u128,
+    pub network: SchemaNetwork,
+    pub sweep_beneficiary: SchemaSweepBeneficiary,
+}
+
+#[derive(BorshSerialize)]
+pub struct SchemaMerkleLeaf {
+    pub address: String,
+    pub amount: u128,
+}
+
+#[derive(BorshSerialize)]
+pub struct SchemaMerkleProof {
+    pub leaf: Vec<u8>,
+    pub proofs: Vec<Vec<u8>>,
+}
+
+//Opcode followed by the borsh bytes packed into little endian u128s, the layout decode_from_ctx reads
+pub fn borsh_inputs<T: BorshSerialize>(opcode: u128, params: &T) -> Result<Vec<u128>> {
+    let bytes = borsh::to_vec(params)?;
+    let mut inputs = vec![opcode];
+    inputs.extend(bytes.chunks(16).map(|chunk| {
+        let mut word = [0u8; 16];
+        word[..chunk.len()].copy_from_slice(chunk);
+        u128::from_le_bytes(word)
+    }));
+    Ok(inputs)
+}
+
+pub fn null_outpoint() -> OutPoint {
+    OutPoint {
+        txid: Txid::from_str("0000000000000000000000000000000000000000000000000000000000000000")
+            .unwrap(),
+        vout: 0,
+    }
+}
+
+pub fn txin(previous_output: OutPoint, witness: Witness) -> TxIn {
+    TxIn {
+        previous_output,
+        script_sig: ScriptBuf::new(),
+        sequence: Sequence::MAX,
+        witness,
+    }
+}
+
+//Leaf paying LEAF_AMOUNT to ADDRESS1, next to a sibling hash so the tree has a 32 byte root
+pub fn airdrop_tree() -> Result<(Vec<u8>, SchemaMerkleProof)> {
+    let leaf = borsh::to_vec(&SchemaMerkleLeaf {
+        address: ADDRESS1(),
+        amount: LEAF_AMOUNT,
+    })?;
+    let sibling = Sha256::digest(b"another leaf").to_vec();
+
+    let (left, right) = if leaf <= sibling {
+        (&leaf, &sibling)
+    } else {
+        (&sibling, &leaf)
+    };
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    let root = hasher.finalize().to_vec();
+
+    Ok((
+        root,
+        SchemaMerkleProof {
+            leaf,
+            proofs: vec![sibling],
+        },
+    ))
+}
+
+//Claims read the proof straight from the witness, so it is not gzipped like a deployment
+fn claim_tx(distributor: AlkaneId, proof: &SchemaMerkleProof) -> Result<bitcoin::Transaction> {
+    let witness = RawEnvelope::from(borsh::to_vec(proof)?).to_witness(false);
+
+    Ok(create_multiple_cellpack_with_witness_and_txins_edicts(
+        vec![Cellpack {
+            target: distributor,
+            inputs: vec![106],
+        }],
+        vec![txin(null_outpoint(), witness)],
+        false,
+        vec![],
+    ))
+}
+
+#[wasm_bindgen_test]
+fn test_merkle_distributor_leaf_claims_once() -> Result<()> {
+    clear();
+
+    let token = AlkaneId { block: 2, tx: 0 };
+    let distributor = AlkaneId { block: 2, tx: 1 };
+    let (merkle_root, proof) = airdrop_tree()?;
+
+    let mut test_block = create_block_with_coinbase_tx(BLOCK_HEIGHT);
+
+    // 1. Deploy the airdropped token, the premine lands on vout 0
+    let deploy_token = create_multiple_cellpack_with_witness_and_txins_edicts(
+        vec![Cellpack {
+            target: AlkaneId { block: 1, tx: 0 },
+            inputs: borsh_inputs(
+                0,
+                &SchemaControlledMintInitializationParameters {
+                    token_name: "AIRDROP".to_string(),
+                    token_symbol: "AIR".to_string(),
+                    premine: AIRDROP_AMOUNT,
+                    cap: AIRDROP_AMOUNT,
+                },
+            )?,
+        }],
+        vec![txin(
+            null_outpoint(),
+            RawEnvelope::from(controlled_mint_build::get_bytes()).to_gzipped_witness(),
+        )],
+        false,
+        vec![],
+    );
+
+    // 2. Deploy the distributor, spending the premine into its initialize call
+    let deploy_distributor = create_multiple_cellpack_with_witness_and_txins_edicts(
+        vec![Cellpack {
+            target: AlkaneId { block: 1, tx: 0 },
+            inputs: borsh_inputs(
+                0,
+                &SchemaInitializeMerkleDistributorParameters {
+                    merkle_root,
+                    alkane_id: SchemaAlkaneId { block: 2, tx: 0 },
+                    amount: AIRDROP_AMOUNT,
+                    block_end: (BLOCK_HEIGHT + 100).into(),
+                    network: SchemaNetwork::Regtest,
+                    sweep_beneficiary: SchemaSweepBeneficiary::Burn,
+                },
+            )?,
+        }],
+        vec![txin(
+            OutPoint {
+                txid: deploy_token.compute_txid(),
+                vout: 0,
+            },
+            RawEnvelope::from(merkle_distributor_build::get_bytes()).to_gzipped_witness(),
+        )],
+        false,
+        //Two real outputs, so the first protomessage is vout 3
+        vec![ProtostoneEdict {
+            id: token.into(),
+            amount: AIRDROP_AMOUNT,
+            output: 3,
+        }],
+    );
+
+    // 3. Claim the leaf, then 4. claim the same leaf again
+    let first_claim = claim_tx(distributor, &proof)?;
+    let second_claim = claim_tx(distributor, &proof)?;
+
+    test_block.txdata.extend([
+        deploy_token,
+        deploy_distributor,
+        first_claim,
+        second_claim,
+    ]);
+    index_block(&test_block, BLOCK_HEIGHT)?;
+
+    let token_id: ProtoruneRuneId = token.into();
+
+    let first_sheet = get_sheet_for_outpoint(&test_block, 3, 0)?;
+    assert_eq!(first_sheet.get_cached(&token_id), LEAF_AMOUNT);
+
+    //The leaf is marked used by the first claim, so the second one reverts and pays nothing
+    let second_sheet = get_sheet_for_outpoint(&test_block, 4, 0)?;
+    assert_eq!(second_sheet.get_cached(&token_id), 0);
+
+    Ok(())
+}
@@ -6,8 +6,12 @@
 #[cfg(any(feature = "test-utils", test))]
 pub mod helpers;
 #[cfg(test)]
+pub mod merkle_distributor;
+#[cfg(test)]
 pub mod std;
 #[cfg(test)]
+pub mod tortilla_airdrop;
+#[cfg(test)]
 pub mod utils;
 
 use wasm_bindgen_test::*;
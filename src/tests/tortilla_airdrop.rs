@@ -0,0 +1,147 @@
+// tacoclicker-rs/src/tests/tortilla_airdrop.rs
+// E2E tests for claiming the TORTILLA airdrop through the Tortilla contract
+// Deploys the mint and distributor templates, initializes Tortilla with its own one leaf tree and claims through it
+
+use crate::tests::helpers::{
+    clear, create_multiple_cellpack_with_witness_and_txins_edicts, get_sheet_for_outpoint,
+};
+use crate::tests::merkle_distributor::{
+    airdrop_tree, borsh_inputs, null_outpoint, txin, SchemaAlkaneId,
+    SchemaControlledMintInitializationParameters, SchemaNetwork, LEAF_AMOUNT,
+};
+use crate::tests::std::{controlled_mint_build, merkle_distributor_build, tacoclicker_build};
+use alkanes::indexer::index_block;
+use alkanes_support::cellpack::Cellpack;
+use alkanes_support::envelope::RawEnvelope;
+use alkanes_support::id::AlkaneId;
+use anyhow::Result;
+use bitcoin::OutPoint;
+use borsh::BorshSerialize;
+use protorune::test_helpers::{create_block_with_coinbase_tx, ADDRESS1};
+use protorune_support::balance_sheet::ProtoruneRuneId;
+use protorune_support::protostone::ProtostoneEdict;
+use wasm_bindgen_test::*;
+
+const BLOCK_HEIGHT: u32 = 840_000;
+const CLAIM_TORTILLA_AIRDROP: u128 = 158;
+
+#[derive(BorshSerialize)]
+pub struct SchemaTacoClickerInitializationParameters {
+    pub controlled_mint_factory: SchemaAlkaneId,
+    pub merkle_distributor_factory: SchemaAlkaneId,
+    pub network: SchemaNetwork,
+    pub funding_address: String,
+    pub airdrop_merkle_root: Option<Vec<u8>>,
+}
+
+#[wasm_bindgen_test]
+fn test_tortilla_airdrop_claim_with_edicts() -> Result<()> {
+    clear();
+
+    //Tortilla clones the token, the distributor and its admin alkane in that order
+    let tortilla = AlkaneId { block: 2, tx: 2 };
+    let tortilla_token = AlkaneId { block: 2, tx: 3 };
+    let admin = AlkaneId { block: 2, tx: 5 };
+    let (merkle_root, proof) = airdrop_tree()?;
+
+    let mut test_block = create_block_with_coinbase_tx(BLOCK_HEIGHT);
+
+    // 1. Deploy the controlled mint template at 2:0
+    let deploy_mint = create_multiple_cellpack_with_witness_and_txins_edicts(
+        vec![Cellpack {
+            target: AlkaneId { block: 1, tx: 0 },
+            inputs: borsh_inputs(
+                0,
+                &SchemaControlledMintInitializationParameters {
+                    token_name: "TEMPLATE".to_string(),
+                    token_symbol: "TMPL".to_string(),
+                    premine: 1,
+                    cap: 1,
+                },
+            )?,
+        }],
+        vec![txin(
+            null_outpoint(),
+            RawEnvelope::from(controlled_mint_build::get_bytes()).to_gzipped_witness(),
+        )],
+        false,
+        vec![],
+    );
+
+    // 2. Deploy the distributor template at 2:1, GetName runs without initializing it
+    let deploy_distributor = create_multiple_cellpack_with_witness_and_txins_edicts(
+        vec![Cellpack {
+            target: AlkaneId { block: 1, tx: 0 },
+            inputs: vec![99],
+        }],
+        vec![txin(
+            null_outpoint(),
+            RawEnvelope::from(merkle_distributor_build::get_bytes()).to_gzipped_witness(),
+        )],
+        false,
+        vec![],
+    );
+
+    // 3. Deploy Tortilla at 2:2, the admin alkane lands on vout 0
+    let deploy_tortilla = create_multiple_cellpack_with_witness_and_txins_edicts(
+        vec![Cellpack {
+            target: AlkaneId { block: 1, tx: 0 },
+            inputs: borsh_inputs(
+                0,
+                &SchemaTacoClickerInitializationParameters {
+                    controlled_mint_factory: SchemaAlkaneId { block: 2, tx: 0 },
+                    merkle_distributor_factory: SchemaAlkaneId { block: 2, tx: 1 },
+                    network: SchemaNetwork::Regtest,
+                    funding_address: ADDRESS1(),
+                    airdrop_merkle_root: Some(merkle_root),
+                },
+            )?,
+        }],
+        vec![txin(
+            null_outpoint(),
+            RawEnvelope::from(tacoclicker_build::get_bytes()).to_gzipped_witness(),
+        )],
+        false,
+        vec![],
+    );
+
+    // 4. Claim through Tortilla with an edict in the message, the distributor only refuses edicts
+    //    on direct claims
+    let claim = create_multiple_cellpack_with_witness_and_txins_edicts(
+        vec![Cellpack {
+            target: tortilla,
+            inputs: vec![CLAIM_TORTILLA_AIRDROP],
+        }],
+        vec![txin(
+            OutPoint {
+                txid: deploy_tortilla.compute_txid(),
+                vout: 0,
+            },
+            RawEnvelope::from(borsh::to_vec(&proof)?).to_witness(false),
+        )],
+        false,
+        //Two real outputs, so the first protomessage is vout 3
+        vec![ProtostoneEdict {
+            id: admin.into(),
+            amount: 1,
+            output: 3,
+        }],
+    );
+
+    test_block.txdata.extend([
+        deploy_mint,
+        deploy_distributor,
+        deploy_tortilla,
+        claim,
+    ]);
+    index_block(&test_block, BLOCK_HEIGHT)?;
+
+    //The airdrop and the forwarded admin alkane both end up on the pointer output
+    let sheet = get_sheet_for_outpoint(&test_block, 4, 0)?;
+    let tortilla_token_id: ProtoruneRuneId = tortilla_token.into();
+    let admin_id: ProtoruneRuneId = admin.into();
+    assert_eq!(sheet.get_cached(&tortilla_token_id), LEAF_AMOUNT);
+    assert_eq!(sheet.get_cached(&admin_id), 1);
+
+    Ok(())
+}